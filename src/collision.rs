@@ -0,0 +1,282 @@
+use vector2d::Vector2D;
+use crate::four_momentum::FourMomentum;
+use crate::m_vector::MVector;
+use crate::motion::MotionSegment;
+use crate::object_id::ObjectId;
+use crate::spatial_vector::SpatialVector;
+use crate::velocity::gamma;
+
+/// Pieces an accelerating step is checked in. Each piece is approximated by the chord between
+/// its exact end points, and a contact found on it is refined on the exact worldlines.
+const ACCELERATED_STEP_PIECES: usize = 8;
+
+/// Bisection steps refining a contact, enough to reach the precision of the step times.
+const CONTACT_REFINEMENTS: usize = 64;
+
+/// How `MFrame` reacts when two registered objects touch.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum CollisionResponse{
+    /// Collisions are only reported.
+    #[default]
+    None,
+    /// Objects bounce off each other along the line joining their centers.
+    Elastic,
+    /// Objects are replaced by a single object carrying their total four-momentum and
+    /// luminosity, and the payload and spectrum of the first object. The payload of the second
+    /// object is handed out by `MFrame::take_merged_payloads`.
+    Inelastic,
+}
+
+/// Two registered objects touching for the first time.
+#[derive(Copy, Clone, Debug)]
+pub struct CollisionEvent<V = Vector2D<f64>>{
+    event: MVector<f64, V>,
+    first: ObjectId,
+    second: ObjectId,
+    first_center: MVector<f64, V>,
+    second_center: MVector<f64, V>,
+    merged_into: Option<ObjectId>,
+}
+
+impl<V: SpatialVector> CollisionEvent<V>{
+    pub(crate) fn new(contact: &ContactPoint<V>, first: ObjectId, second: ObjectId) -> Self{
+        Self{
+            event: contact.event,
+            first,
+            second,
+            first_center: contact.first_center,
+            second_center: contact.second_center,
+            merged_into: None,
+        }
+    }
+
+    /// Point of first contact, on the line joining both centers.
+    pub fn get_event(&self) -> &MVector<f64, V> {
+        &self.event
+    }
+
+    pub fn get_first(&self) -> ObjectId {
+        self.first
+    }
+
+    pub fn get_second(&self) -> ObjectId {
+        self.second
+    }
+
+    pub fn get_first_center(&self) -> &MVector<f64, V> {
+        &self.first_center
+    }
+
+    pub fn get_second_center(&self) -> &MVector<f64, V> {
+        &self.second_center
+    }
+
+    /// Id of the object both collided objects were merged into by an inelastic response.
+    pub fn get_merged_into(&self) -> Option<ObjectId> {
+        self.merged_into
+    }
+
+    pub(crate) fn set_merged_into(&mut self, id: ObjectId) {
+        self.merged_into = Some(id);
+    }
+}
+
+/// Four-momenta after an elastic collision, or `None` if the objects are already separating.
+///
+/// In the center-of-momentum frame the momenta are mirrored along the line joining the centers,
+/// which keeps both energies and the total four-momentum unchanged.
+pub(crate) fn elastic_response<V: SpatialVector>(first: FourMomentum<V>, second: FourMomentum<V>, first_center: MVector<f64, V>, second_center: MVector<f64, V>) -> Option<(FourMomentum<V>, FourMomentum<V>)>{
+    let cm_velocity = (first + second).velocity();
+    let first_cm = first.lorentz_transform(cm_velocity);
+    let second_cm = second.lorentz_transform(cm_velocity);
+    let first_center = first_center.lorentz_transform(cm_velocity);
+    let second_center = second_center.lorentz_transform(cm_velocity);
+    let first_pos = first_center.pos + first_cm.velocity() * (second_center.time - first_center.time);
+    let normal = (second_center.pos - first_pos).normalise();
+    let approach = V::dot(first_cm.momentum() - second_cm.momentum(), normal);
+    if approach <= 0.0 {
+        return None
+    }
+    let reflect = |p: FourMomentum<V>| {
+        let momentum = p.momentum();
+        FourMomentum::new(p.energy(), momentum - normal * (2.0 * V::dot(momentum, normal)))
+            .lorentz_transform(-cm_velocity)
+    };
+    Some((reflect(first_cm), reflect(second_cm)))
+}
+
+/// Radius of an object with the same volume as both colliding ones together.
+pub(crate) fn merged_radius<V: SpatialVector>(first: f64, second: f64) -> f64{
+    let dim = V::DIM as i32;
+    (first.powi(dim) + second.powi(dim)).powf(1.0 / dim as f64)
+}
+
+/// Piece of an object's worldline covered during one `MFrame::process_time` call.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct WorldlineStep<V>{
+    segment: MotionSegment<V>,
+    start_tau: f64,
+    end_tau: f64,
+    radius: f64,
+    start: MVector<f64, V>,
+    end: MVector<f64, V>,
+}
+
+pub(crate) struct ContactPoint<V>{
+    pub(crate) event: MVector<f64, V>,
+    pub(crate) first_center: MVector<f64, V>,
+    pub(crate) second_center: MVector<f64, V>,
+}
+
+pub(crate) struct Contact<V>{
+    pub(crate) first_contact: Option<ContactPoint<V>>,
+    pub(crate) touching_at_end: bool,
+}
+
+impl<V: SpatialVector> WorldlineStep<V>{
+    pub(crate) fn new(segment: MotionSegment<V>, start_tau: f64, end_tau: f64, radius: f64) -> Self{
+        Self{
+            segment,
+            start_tau,
+            end_tau,
+            radius,
+            start: segment.state_at_tau(start_tau).0,
+            end: segment.state_at_tau(end_tau).0,
+        }
+    }
+
+    /// Cheap test whether the steps can bring both objects within touching distance. Neither
+    /// moves faster than light, so pairs farther apart at the start than twice the time both
+    /// steps span can be skipped; the margin covers the pair frame's different simultaneity.
+    pub(crate) fn may_touch(&self, other: &Self) -> bool{
+        let touch_distance = self.radius + other.radius;
+        if touch_distance <= 0.0 {
+            return false
+        }
+        let gap = (other.start.pos - self.start.pos).length() - (other.start.time - self.start.time).abs() - touch_distance;
+        let durations = (self.end.time - self.start.time).max(0.0) + (other.end.time - other.start.time).max(0.0);
+        gap <= 2.0 * durations
+    }
+
+    /// Position along the first axis at the start, by which steps are swept.
+    pub(crate) fn sweep_key(&self) -> f64{
+        self.start.pos.component(0)
+    }
+
+    /// Distance along the first axis beyond which no two of `steps` pass `may_touch`.
+    pub(crate) fn sweep_reach<'a>(steps: impl Iterator<Item = &'a Self>) -> f64 where V: 'a{
+        let (mut radius, mut duration, mut first_start, mut last_start) = (0.0f64, 0.0f64, f64::INFINITY, f64::NEG_INFINITY);
+        for step in steps {
+            radius = radius.max(step.radius);
+            duration = duration.max(step.end.time - step.start.time);
+            first_start = first_start.min(step.start.time);
+            last_start = last_start.max(step.start.time);
+        }
+        2.0 * radius + 4.0 * duration + (last_start - first_start).max(0.0)
+    }
+
+    fn accelerating(&self) -> bool{
+        self.segment.get_acceleration().length() > 0.0
+    }
+
+    fn four_velocity(&self) -> MVector<f64, V>{
+        let velocity = self.segment.state_at_tau(self.start_tau).1;
+        MVector::new(1.0, velocity) * gamma(velocity)
+    }
+
+    /// Times of the start and the end in a frame moving with `frame_velocity`.
+    fn times_in_frame(&self, frame_velocity: V) -> (f64, f64){
+        let time_at = |tau| self.segment.state_at_tau(tau).0.lorentz_transform(frame_velocity).time;
+        (time_at(self.start_tau), time_at(self.end_tau))
+    }
+
+    /// Position at time `time` of a frame moving with `frame_velocity`, in that frame.
+    fn position_at(&self, time: f64, frame_velocity: V) -> V{
+        let tau = self.segment.tau_at_frame_time(time, frame_velocity);
+        self.segment.state_at_tau(tau).0.lorentz_transform(frame_velocity).pos
+    }
+}
+
+/// Checks two steps for contact in the frame where the sum of their four-velocities at the
+/// start is at rest. The criterion only depends on the worldlines, so every observer agrees on
+/// it. Positions are exact on the motion segments, also while the objects accelerate.
+pub(crate) fn detect_contact<V: SpatialVector>(a: &WorldlineStep<V>, b: &WorldlineStep<V>) -> Option<Contact<V>>{
+    let touch_distance = a.radius + b.radius;
+    if touch_distance <= 0.0 || a.end_tau <= a.start_tau || b.end_tau <= b.start_tau {
+        return None
+    }
+    let four_velocity_sum = a.four_velocity() + b.four_velocity();
+    let pair_velocity = four_velocity_sum.pos / four_velocity_sum.time;
+    let separation_at = |t: f64| b.position_at(t, pair_velocity) - a.position_at(t, pair_velocity);
+
+    let ((a_start, a_end), (b_start, b_end)) = (a.times_in_frame(pair_velocity), b.times_in_frame(pair_velocity));
+    let t_from = a_start.min(b_start);
+    // Steps are not simultaneous in the pair frame. Ending at the earliest end makes the next
+    // steps start exactly where these end, so a lasting contact is never reported twice.
+    let t_to = a_end.min(b_end);
+
+    let pieces = if a.accelerating() || b.accelerating() { ACCELERATED_STEP_PIECES } else { 1 };
+    let piece = (t_to - t_from).max(0.0) / pieces as f64;
+    let contact_time = (0..pieces).find_map(|i| {
+        let (from, to) = (t_from + piece * i as f64, t_from + piece * (i + 1) as f64);
+        let separation = separation_at(from);
+        let separation_velocity = if piece > 0.0 { (separation_at(to) - separation) / piece } else { V::zero() };
+        let dt = first_time_within(separation, separation_velocity, touch_distance)
+            .filter(|dt| *dt <= piece)?;
+        if pieces == 1 {
+            return Some(from + dt)
+        }
+        refine_contact(separation_at, from, from + dt, to, touch_distance)
+    });
+    let first_contact = contact_time.map(|t| {
+        let a_pos = a.position_at(t, pair_velocity);
+        let b_pos = b.position_at(t, pair_velocity);
+        let contact = a_pos + (b_pos - a_pos) * (a.radius / touch_distance);
+        ContactPoint{
+            event: MVector::new(t, contact).lorentz_transform(-pair_velocity),
+            first_center: MVector::new(t, a_pos).lorentz_transform(-pair_velocity),
+            second_center: MVector::new(t, b_pos).lorentz_transform(-pair_velocity),
+        }
+    });
+    Some(Contact{
+        first_contact,
+        touching_at_end: separation_at(t_to).length() <= touch_distance,
+    })
+}
+
+/// First time in `[from, to]` at which the exact separation is within `distance`, found by
+/// bisection from the estimate `guess` of the chord. `None` if the worldlines stay apart at
+/// both `guess` and `to`.
+fn refine_contact<V: SpatialVector>(separation_at: impl Fn(f64) -> V, from: f64, guess: f64, to: f64, distance: f64) -> Option<f64>{
+    let within = |t: f64| separation_at(t).length() <= distance;
+    if within(from) {
+        return Some(from)
+    }
+    let (mut apart, mut touching) = (from, [guess, to].into_iter().find(|t| within(*t))?);
+    for _i in 0..CONTACT_REFINEMENTS {
+        let middle = (apart + touching) / 2.0;
+        if within(middle) {
+            touching = middle;
+        } else {
+            apart = middle;
+        }
+    }
+    Some(touching)
+}
+
+fn first_time_within<V: SpatialVector>(separation: V, separation_velocity: V, distance: f64) -> Option<f64>{
+    let c = separation.length_squared() - distance * distance;
+    if c <= 0.0 {
+        return Some(0.0)
+    }
+    let a = separation_velocity.length_squared();
+    let half_b = V::dot(separation, separation_velocity);
+    if a == 0.0 || half_b >= 0.0 {
+        return None
+    }
+    let discriminant = half_b * half_b - a * c;
+    if discriminant < 0.0 {
+        return None
+    }
+    Some((-half_b - discriminant.sqrt()) / a)
+}
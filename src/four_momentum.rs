@@ -0,0 +1,95 @@
+use std::ops::{Add, Sub};
+use vector2d::Vector2D;
+use crate::lorentz_transform::LorentzTransform;
+use crate::m_vector::MVector;
+use crate::spatial_vector::SpatialVector;
+use crate::velocity::gamma;
+
+/// Energy-momentum four-vector: `time` holds the energy and `pos` the momentum (c = 1).
+#[derive(Copy, Clone, Default, Debug)]
+pub struct FourMomentum<V = Vector2D<f64>>{
+    m_vector: MVector<f64, V>,
+}
+
+impl<V: SpatialVector> Add for FourMomentum<V>{
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::from_m_vector(self.m_vector + rhs.m_vector)
+    }
+}
+
+impl<V: SpatialVector> Sub for FourMomentum<V>{
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::from_m_vector(self.m_vector - rhs.m_vector)
+    }
+}
+
+impl<V: SpatialVector> FourMomentum<V>{
+    pub fn new(energy: f64, momentum: V) -> Self{
+        Self::from_m_vector(MVector::new(energy, momentum))
+    }
+
+    pub fn from_m_vector(m_vector: MVector<f64, V>) -> Self{
+        Self{
+            m_vector,
+        }
+    }
+
+    pub fn from_mass_and_velocity(rest_mass: f64, velocity: V) -> Self{
+        let energy = rest_mass * gamma(velocity);
+        Self::new(energy, velocity * energy)
+    }
+
+    pub fn get_m_vector(&self) -> &MVector<f64, V> {
+        &self.m_vector
+    }
+
+    pub fn energy(&self) -> f64{
+        self.m_vector.time
+    }
+
+    pub fn momentum(&self) -> V{
+        self.m_vector.pos
+    }
+
+    /// Invariant mass, the same in every frame.
+    pub fn mass(&self) -> f64{
+        self.m_vector.length_squared().max(0.0).sqrt()
+    }
+
+    pub fn kinetic_energy(&self) -> f64{
+        self.energy() - self.mass()
+    }
+
+    /// Velocity of the frame in which the momentum vanishes.
+    pub fn velocity(&self) -> V{
+        if self.energy() == 0.0 {
+            return V::zero()
+        }
+        self.momentum() / self.energy()
+    }
+
+    pub fn lorentz_transform(&self, velocity: V) -> Self{
+        Self::from_m_vector(self.m_vector.lorentz_transform(velocity))
+    }
+
+    pub fn transform(&self, transform: &LorentzTransform<V>) -> Self{
+        Self::from_m_vector(transform.apply(&self.m_vector))
+    }
+}
+
+#[test]
+fn four_momentum_invariant_mass() {
+    let p = FourMomentum::from_mass_and_velocity(2.0, Vector2D::new(0.6, 0.0));
+
+    assert!((p.energy() - 2.5).abs() < 1e-9);
+    assert!((p.kinetic_energy() - 0.5).abs() < 1e-9);
+
+    let in_rest_frame = p.lorentz_transform(p.velocity());
+    assert!((in_rest_frame.energy() - 2.0).abs() < 1e-9);
+    assert!(in_rest_frame.momentum().length() < 1e-9);
+    assert!((p.lorentz_transform(Vector2D::new(-0.3, 0.5)).mass() - 2.0).abs() < 1e-9);
+}
@@ -0,0 +1,184 @@
+use vector2d::Vector2D;
+use crate::m_object::MObject;
+use crate::m_vector::MVector;
+use crate::motion::MotionSegment;
+use crate::object_tracker::ObjectTracker;
+use crate::spatial_vector::SpatialVector;
+use crate::MAX_SAFE_SPEED;
+
+const MAX_BRACKET_STEPS: usize = 64;
+const BISECTION_STEPS: usize = 100;
+
+/// Launch solution for a projectile fired from the shooter's current event.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Intercept<V = Vector2D<f64>>{
+    event: MVector<f64, V>,
+    target_tau: f64,
+    launch_velocity: V,
+    base_velocity: V,
+    flight_time: f64,
+}
+
+impl<V: SpatialVector> Intercept<V>{
+    /// Event at which the projectile meets the target.
+    pub fn get_event(&self) -> &MVector<f64, V> {
+        &self.event
+    }
+
+    /// Proper time of the target at the intercept.
+    pub fn get_target_tau(&self) -> f64 {
+        self.target_tau
+    }
+
+    /// Projectile velocity in the shooter's rest frame, as taken by `MFrame::spawn_projectile`.
+    pub fn get_launch_velocity(&self) -> &V {
+        &self.launch_velocity
+    }
+
+    pub fn get_direction(&self) -> V {
+        self.launch_velocity.normalise()
+    }
+
+    /// Projectile velocity in the base frame.
+    pub fn get_base_velocity(&self) -> &V {
+        &self.base_velocity
+    }
+
+    /// Flight time measured by the shooter.
+    pub fn get_flight_time(&self) -> f64 {
+        self.flight_time
+    }
+}
+
+/// Aims at the actual current motion of `target`.
+pub fn intercept_object<V: SpatialVector>(shooter: &MObject<V>, target: &MObject<V>, projectile_speed: f64) -> Option<Intercept<V>>{
+    intercept(shooter, target.get_motion_segment(), projectile_speed)
+}
+
+/// Aims at what the shooter sees of a target, assuming it keeps the motion it was last seen
+/// with. `seen` must track the target as seen by `shooter`.
+pub fn intercept_seen<V: SpatialVector>(shooter: &MObject<V>, seen: &ObjectTracker<V>, projectile_speed: f64) -> Option<Intercept<V>>{
+    intercept(shooter, seen.get_visible_segment()?, projectile_speed)
+}
+
+/// Earliest intercept of a target moving along `target` by a projectile fired from the
+/// shooter's current event with `projectile_speed` in the shooter's rest frame. `None` if
+/// the projectile can never catch the target.
+pub fn intercept<V: SpatialVector>(shooter: &MObject<V>, target: &MotionSegment<V>, projectile_speed: f64) -> Option<Intercept<V>>{
+    let speed = projectile_speed.min(MAX_SAFE_SPEED);
+    if speed <= 0.0 {
+        return None
+    }
+    let origin = *shooter.get_m_pos();
+    let frame_velocity = *shooter.get_velocity();
+    let shot_time = origin.lorentz_transform(frame_velocity).time;
+    // Target's proper time and position relative to the shot, `t` after it in the shooter frame.
+    let target_at = |t: f64| {
+        let tau = target.tau_at_frame_time(shot_time + t, frame_velocity);
+        (tau, (target.state_at_tau(tau).0 - origin).lorentz_transform(frame_velocity))
+    };
+    // Positive once the projectile can reach the target event in time.
+    let reach = |t: f64| {
+        let d = target_at(t).1;
+        speed * d.time - d.pos.length()
+    };
+
+    if !target_at(0.0).0.is_finite() {
+        return None
+    }
+    // Steps forward in shooter time. A fast target may only be in reach for a short while,
+    // so once the reach shrinks its maximum is searched for a root before it.
+    let mut step = target_at(0.0).1.pos.length().max(1e-9);
+    let (mut previous, mut low, mut high) = (0.0, 0.0, 0.0);
+    let mut bracketed = reach(0.0) >= 0.0;
+    for _ in 0..MAX_BRACKET_STEPS {
+        if bracketed {
+            break
+        }
+        high = low + step;
+        if reach(high) >= 0.0 {
+            bracketed = true;
+        } else if reach(high) < reach(low) && let best = largest_reach(reach, previous, high) && reach(best) >= 0.0 {
+            (low, high) = (previous, best);
+            bracketed = true;
+        } else {
+            (previous, low) = (low, high);
+            step *= 2.0;
+        }
+    }
+    if !bracketed {
+        return None
+    }
+    for _ in 0..BISECTION_STEPS {
+        let middle = (low + high) / 2.0;
+        if reach(middle) >= 0.0 {
+            high = middle;
+        } else {
+            low = middle;
+        }
+    }
+
+    let (target_tau, d) = target_at(high);
+    // A target at the shooter's event is hit in any direction.
+    let direction = match d.pos.length_squared() {
+        x if x > 0.0 => d.pos.normalise(),
+        _ => V::axis(0),
+    };
+    let launch_velocity = direction * speed;
+    Some(Intercept{
+        event: target.state_at_tau(target_tau).0,
+        target_tau,
+        launch_velocity,
+        base_velocity: shooter.velocity_from_rest_frame(launch_velocity),
+        flight_time: d.time,
+    })
+}
+
+/// Time in `[from, to]` at which `reach` is largest, by golden section search.
+fn largest_reach(reach: impl Fn(f64) -> f64, mut from: f64, mut to: f64) -> f64{
+    let ratio = (5.0f64.sqrt() - 1.0) / 2.0;
+    for _ in 0..BISECTION_STEPS {
+        let (left, right) = (to - (to - from) * ratio, from + (to - from) * ratio);
+        if reach(left) < reach(right) {
+            from = left;
+        } else {
+            to = right;
+        }
+    }
+    (from + to) / 2.0
+}
+
+#[test]
+fn intercept_of_constant_velocity_target() {
+    let shooter = MObject::new(MVector::zero(), Vector2D::new(0.0, 0.0), true, 0.0, 1.0);
+    let target = MotionSegment::new(MVector::new(0.0, Vector2D::new(0.0, 3.0)), 0.0, Vector2D::new(0.6, 0.0), Vector2D::new(0.0, 0.0));
+    let solution = intercept(&shooter, &target, 0.75).unwrap_or_default();
+
+    // (0.6 t)^2 + 3^2 = (0.75 t)^2
+    assert!((solution.get_event().time - 20.0 / 3.0).abs() < 1e-9);
+    assert!((solution.get_event().pos.x - 4.0).abs() < 1e-9);
+    assert!((solution.get_target_tau() - 16.0 / 3.0).abs() < 1e-9);
+    assert!((solution.get_launch_velocity().length() - 0.75).abs() < 1e-9);
+    assert!(intercept(&shooter, &target, 0.5).is_none());
+
+    // A fast target passing by is only in reach for a while after the first step.
+    let passing = MotionSegment::new(MVector::new(0.0, Vector2D::new(10.0, 0.0)), 0.0, Vector2D::new(-0.99, 0.0), Vector2D::new(0.0, 0.0));
+    let solution = intercept(&shooter, &passing, 0.5).unwrap_or_default();
+    // 10 - 0.99 t = 0.5 t
+    assert!((solution.get_event().time - 10.0 / 1.49).abs() < 1e-9);
+    assert!((solution.get_launch_velocity().x - 0.5).abs() < 1e-9);
+
+    // Passing at an angle, it is in reach only for a short while before the first step ends.
+    let angle = 17.5f64.to_radians();
+    let velocity = Vector2D::new(-angle.cos(), angle.sin()) * 0.99;
+    let passing_by = MotionSegment::new(MVector::new(0.0, Vector2D::new(10.0, 0.0)), 0.0, velocity, Vector2D::new(0.0, 0.0));
+    let solution = intercept(&shooter, &passing_by, 0.3).unwrap_or_default();
+    // (0.3 t)^2 = (10 + v.x t)^2 + (v.y t)^2
+    let (a, b, c) = (0.99f64 * 0.99 - 0.09, 20.0 * velocity.x, 100.0);
+    assert!((solution.get_event().time - (-b - (b * b - 4.0 * a * c).sqrt()) / (2.0 * a)).abs() < 1e-9);
+
+    let at_shooter = MotionSegment::new(MVector::zero(), 0.0, Vector2D::new(0.3, 0.0), Vector2D::new(0.0, 0.0));
+    let solution = intercept(&shooter, &at_shooter, 0.75).unwrap_or_default();
+    assert_eq!(solution.get_flight_time(), 0.0);
+    assert!((solution.get_launch_velocity().length() - 0.75).abs() < 1e-9);
+}
@@ -6,12 +6,26 @@
 #![deny(clippy::unwrap_used)]
 #![deny(clippy::expect_used)]
 
-pub static UPDATE_RATIO: f64 = 1.0/120.0;
+pub static DEFAULT_UPDATE_RATIO: f64 = 1.0/120.0;
+
+/// Samples kept waiting per emitting point before a tracker starts thinning its queue.
+pub static DEFAULT_PHOTON_QUEUE_BUDGET: usize = 1024;
 
 pub static MAX_SAFE_SPEED: f64 = 1.0 - 10e-6;
 
 pub mod m_vector;
+pub mod spatial_vector;
+pub mod vector3d;
 pub mod m_object;
+pub mod velocity;
+pub mod motion;
+pub mod worldline;
+pub mod lorentz_transform;
+pub mod four_momentum;
+pub mod spectrum;
 pub mod object_tracker;
+pub mod object_id;
+pub mod collision;
+pub mod intercept;
 mod photon;
 pub mod m_frame;
\ No newline at end of file
@@ -0,0 +1,210 @@
+use std::marker::PhantomData;
+use std::ops::Mul;
+use vector2d::Vector2D;
+use crate::m_vector::MVector;
+use crate::spatial_vector::SpatialVector;
+use crate::vector3d::Vector3D;
+use crate::velocity::{add_velocities, gamma};
+
+const SIZE: usize = 4;
+
+/// Proper orthochronous Lorentz transformation: a boost followed by a spatial rotation.
+///
+/// Like `MVector::lorentz_transform`, it maps coordinates of an event in the base frame
+/// to coordinates of the same event in the transformed frame. Index 0 of the matrix is
+/// time, the following `V::DIM` indices are space.
+#[derive(Copy, Clone, Debug)]
+pub struct LorentzTransform<V = Vector2D<f64>>{
+    matrix: [[f64; SIZE]; SIZE],
+    _space: PhantomData<V>,
+}
+
+impl<V: SpatialVector> Default for LorentzTransform<V>{
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl<V: SpatialVector> Mul for LorentzTransform<V>{
+    type Output = Self;
+
+    /// `a * b` applies `b` first and `a` second.
+    fn mul(self, rhs: Self) -> Self::Output {
+        let mut matrix = [[0.0; SIZE]; SIZE];
+        for (r, row) in matrix.iter_mut().enumerate() {
+            for (c, value) in row.iter_mut().enumerate() {
+                *value = (0..SIZE).map(|k| self.matrix[r][k] * rhs.matrix[k][c]).sum();
+            }
+        }
+        Self::from_matrix(matrix)
+    }
+}
+
+impl<V: SpatialVector> LorentzTransform<V>{
+
+    pub fn identity() -> Self{
+        let mut matrix = [[0.0; SIZE]; SIZE];
+        for (i, row) in matrix.iter_mut().enumerate() {
+            row[i] = 1.0;
+        }
+        Self::from_matrix(matrix)
+    }
+
+    /// Pure boost into the frame moving with `velocity`.
+    pub fn boost(velocity: V) -> Self{
+        let mut res = Self::identity();
+        let v_squared = velocity.length_squared();
+        if v_squared == 0.0 {
+            return res
+        }
+        let gamma = gamma(velocity);
+        res.matrix[0][0] = gamma;
+        for i in 0..V::DIM {
+            let v_i = velocity.component(i);
+            res.matrix[0][i + 1] = -gamma * v_i;
+            res.matrix[i + 1][0] = -gamma * v_i;
+            for j in 0..V::DIM {
+                res.matrix[i + 1][j + 1] += (gamma - 1.0) * v_i * velocity.component(j) / v_squared;
+            }
+        }
+        res
+    }
+
+    /// Pure rotation of spatial coordinates, given by the images of the base axes.
+    pub fn rotation(axes_images: &[V]) -> Self{
+        let mut res = Self::identity();
+        for (c, image) in axes_images.iter().enumerate().take(V::DIM) {
+            for r in 0..V::DIM {
+                res.matrix[r + 1][c + 1] = image.component(r);
+            }
+        }
+        res
+    }
+
+    /// Rotation left over after boosting by `first` and then by `second`, where `second`
+    /// is measured in the frame reached by the first boost.
+    pub fn wigner_rotation(first: V, second: V) -> Self{
+        Self::boost(first).then(&Self::boost(second)).rotation_part()
+    }
+
+    /// Transformation equivalent to applying `self` and then `next`.
+    pub fn then(&self, next: &Self) -> Self{
+        *next * *self
+    }
+
+    pub fn inverse(&self) -> Self{
+        let mut matrix = [[0.0; SIZE]; SIZE];
+        for (r, row) in matrix.iter_mut().enumerate() {
+            for (c, value) in row.iter_mut().enumerate() {
+                let sign = if (r == 0) == (c == 0) { 1.0 } else { -1.0 };
+                *value = sign * self.matrix[c][r];
+            }
+        }
+        Self::from_matrix(matrix)
+    }
+
+    pub fn apply(&self, m_vector: &MVector<f64, V>) -> MVector<f64, V>{
+        let input: Vec<f64> = std::iter::once(m_vector.time)
+            .chain((0..V::DIM).map(|i| m_vector.pos.component(i)))
+            .collect();
+        let output: Vec<f64> = (0..=V::DIM)
+            .map(|r| (0..=V::DIM).map(|c| self.matrix[r][c] * input[c]).sum())
+            .collect();
+        MVector::new(output[0], V::from_components(&output[1..]))
+    }
+
+    /// Velocity of the transformed frame measured in the base frame.
+    pub fn velocity(&self) -> V{
+        let components: Vec<f64> = (0..V::DIM)
+            .map(|i| -self.matrix[0][i + 1] / self.matrix[0][0])
+            .collect();
+        V::from_components(&components)
+    }
+
+    pub fn boost_part(&self) -> Self{
+        Self::boost(self.velocity())
+    }
+
+    /// Rotation `R` such that `self == R * self.boost_part()`.
+    pub fn rotation_part(&self) -> Self{
+        *self * self.boost_part().inverse()
+    }
+
+    pub fn rotate_vector(&self, vector: &V) -> V{
+        self.rotation_part().apply(&MVector::new(0.0, *vector)).pos
+    }
+
+    pub fn get_matrix(&self) -> &[[f64; SIZE]; SIZE] {
+        &self.matrix
+    }
+
+    fn from_matrix(matrix: [[f64; SIZE]; SIZE]) -> Self{
+        Self{
+            matrix,
+            _space: PhantomData,
+        }
+    }
+}
+
+impl LorentzTransform<Vector2D<f64>>{
+    pub fn from_angle(angle: f64) -> Self{
+        let (sin, cos) = angle.sin_cos();
+        Self::rotation(&[Vector2D::new(cos, sin), Vector2D::new(-sin, cos)])
+    }
+
+    /// Angle of the rotation part.
+    pub fn rotation_angle(&self) -> f64{
+        let rotation = self.rotation_part();
+        rotation.matrix[2][1].atan2(rotation.matrix[1][1])
+    }
+}
+
+impl LorentzTransform<Vector3D<f64>>{
+    pub fn from_axis_angle(axis: Vector3D<f64>, angle: f64) -> Self{
+        let axis = axis.normalise();
+        let (sin, cos) = angle.sin_cos();
+        let rotate = |v: Vector3D<f64>| {
+            v * cos + Vector3D::cross(axis, v) * sin + axis * (Vector3D::dot(axis, v) * (1.0 - cos))
+        };
+        Self::rotation(&[
+            rotate(Vector3D::new(1.0, 0.0, 0.0)),
+            rotate(Vector3D::new(0.0, 1.0, 0.0)),
+            rotate(Vector3D::new(0.0, 0.0, 1.0)),
+        ])
+    }
+}
+
+impl<V: SpatialVector> MVector<f64, V> {
+    pub fn transform(&self, transform: &LorentzTransform<V>) -> Self{
+        transform.apply(self)
+    }
+}
+
+#[test]
+fn boost_matches_lorentz_transform() {
+    let p = MVector::new(3.0, Vector2D::new(1.0, 2.0));
+    let v = Vector2D::new(0.6, 0.2);
+    let expected = p.lorentz_transform(v);
+    let actual = LorentzTransform::boost(v).apply(&p);
+
+    assert!((expected.time - actual.time).abs() < 1e-9);
+    assert!((expected.pos - actual.pos).length() < 1e-9);
+}
+
+#[test]
+fn wigner_rotation_of_perpendicular_boosts() {
+    let (u, w) = (0.6, 0.8);
+    let chain = LorentzTransform::boost(Vector2D::new(u, 0.0)).then(&LorentzTransform::boost(Vector2D::new(0.0, w)));
+    let (gamma_u, gamma_w) = (1.0/(1.0 - u * u).sqrt(), 1.0/(1.0 - w * w).sqrt());
+    let expected_cos = (gamma_u + gamma_w) / (1.0 + gamma_u * gamma_w);
+
+    assert!((chain.rotation_angle().abs().cos() - expected_cos).abs() < 1e-9);
+    assert!((chain.velocity() - add_velocities(Vector2D::new(u, 0.0), Vector2D::new(0.0, w))).length() < 1e-9);
+    assert!((LorentzTransform::wigner_rotation(Vector2D::new(u, 0.0), Vector2D::new(0.0, w)).rotation_angle() - chain.rotation_angle()).abs() < 1e-9);
+
+    let p = MVector::new(1.5, Vector2D::new(-0.3, 2.0));
+    let back = chain.inverse().apply(&chain.apply(&p));
+    assert!((back.time - p.time).abs() < 1e-9);
+    assert!((back.pos - p.pos).length() < 1e-9);
+    assert!((chain.apply(&p).length_squared() - p.length_squared()).abs() < 1e-9);
+}
@@ -1,74 +1,599 @@
 use rayon::iter::{IntoParallelRefMutIterator, ParallelIterator};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use rayon::iter::IntoParallelRefIterator;
 use vector2d::Vector2D;
+use crate::collision::{detect_contact, elastic_response, merged_radius, CollisionEvent, CollisionResponse, WorldlineStep};
 use crate::m_object::MObject;
 use crate::m_vector::MVector;
-use crate::object_tracker::{ObjectTracker, ReceiverData};
+use crate::object_id::{ObjectId, ObjectIdAllocator};
+use crate::photon::Photon;
+use crate::object_tracker::{ObjectTracker, PhotonQueueStats, ReceiverData};
+use crate::spatial_vector::SpatialVector;
+use crate::{DEFAULT_PHOTON_QUEUE_BUDGET, DEFAULT_UPDATE_RATIO};
 
-#[derive(Default)]
-pub struct MFrame{
+/// Observer's view of a world of registered objects, each stored with a user payload `T`.
+pub struct MFrame<V = Vector2D<f64>, T = ()>{
 
-    frame_object: MObject,
+    frame_object: MObject<V>,
+    frame_data: T,
 
-    registered_objects: HashMap<usize, (MObject, ObjectTracker)>,
+    registered_objects: HashMap<ObjectId, (MObject<V>, ObjectTracker<V>, T)>,
+    destroyed_objects: HashMap<ObjectId, (MObject<V>, ObjectTracker<V>)>,
+    observers: HashMap<ObjectId, HashMap<ObjectId, ObjectTracker<V>>>,
+    observed_frame_object: HashMap<ObjectId, ObjectTracker<V>>,
 
-    counter: usize
+    ids: ObjectIdAllocator,
+    update_ratio: f64,
+    photon_queue_budget: usize,
+
+    contacts: HashSet<(ObjectId, ObjectId)>,
+    collision_events: Vec<CollisionEvent<V>>,
+    merged_payloads: Vec<(ObjectId, T)>,
+    collision_response: CollisionResponse,
+    collision_detection: bool,
 }
 
-impl MFrame{
+impl<V: SpatialVector, T: Default + Send + Sync> Default for MFrame<V, T>{
+    fn default() -> Self {
+        Self::with_frame_data(DEFAULT_UPDATE_RATIO, T::default())
+    }
+}
+
+impl<V: SpatialVector> MFrame<V>{
 
     pub fn new() -> Self{
+        Self::with_update_ratio(DEFAULT_UPDATE_RATIO)
+    }
+
+    pub fn with_update_ratio(update_ratio: f64) -> Self{
+        Self::with_frame_data(update_ratio, ())
+    }
+}
+
+impl<V: SpatialVector, T: Default + Send + Sync> MFrame<V, T>{
+
+    /// Creates an object at the event `initial_pos`. It stays invisible to each observer until
+    /// light from that event reaches them.
+    pub fn register_object(&mut self, initial_pos: MVector<f64, V>, initial_vel: V, constant_velocity: bool, radius: f64) -> ObjectId{
+        self.register_object_with_data(initial_pos, initial_vel, constant_velocity, radius, self.update_ratio, T::default())
+    }
+
+    /// Like `register_object`, ticking every `update_ratio` of the object's proper time
+    /// instead of the frame's update ratio, see `MObject::get_update_ratio`.
+    pub fn register_object_with_update_ratio(&mut self, initial_pos: MVector<f64, V>, initial_vel: V, constant_velocity: bool, radius: f64, update_ratio: f64) -> ObjectId{
+        self.register_object_with_data(initial_pos, initial_vel, constant_velocity, radius, update_ratio, T::default())
+    }
+
+    /// Creates an object at the current event of `shooter`, moving with `launch_velocity` in the
+    /// shooter's rest frame. Returns `None` if there is no object `shooter`.
+    pub fn spawn_projectile(&mut self, shooter: &ObjectId, launch_velocity: V, constant_velocity: bool, radius: f64) -> Option<ObjectId>{
+        self.spawn_projectile_with_data(shooter, launch_velocity, constant_velocity, radius, T::default())
+    }
+
+    /// Like `spawn_projectile`, fired by the frame object.
+    pub fn spawn_projectile_from_frame_object(&mut self, launch_velocity: V, constant_velocity: bool, radius: f64) -> ObjectId{
+        let event = *self.frame_object.get_m_pos();
+        let velocity = self.frame_object.velocity_from_rest_frame(launch_velocity);
+        self.register_object(event, velocity, constant_velocity, radius)
+    }
+}
+
+impl<V: SpatialVector, T: Send + Sync> MFrame<V, T>{
+
+    /// Frame whose observer carries the payload `frame_data`.
+    pub fn with_frame_data(update_ratio: f64, frame_data: T) -> Self{
         Self{
-            frame_object: Default::default(),
+            frame_object: MObject::new(MVector::zero(), V::zero(), false, 0.0, update_ratio),
+            frame_data,
             registered_objects: Default::default(),
-            counter: 0,
+            destroyed_objects: Default::default(),
+            observers: Default::default(),
+            observed_frame_object: Default::default(),
+            ids: Default::default(),
+            update_ratio,
+            photon_queue_budget: DEFAULT_PHOTON_QUEUE_BUDGET,
+            contacts: Default::default(),
+            collision_events: vec![],
+            merged_payloads: vec![],
+            collision_response: CollisionResponse::None,
+            collision_detection: true,
         }
     }
 
-    pub fn register_object(&mut self, initial_pos: MVector<f64>, initial_vel: Vector2D<f64>, constant_velocity: bool, radius: f64) -> usize{
-        let mut m_object = MObject::new(initial_pos, initial_vel, constant_velocity, radius);
-        let mut object_tracker = ObjectTracker::new();
-        let id = self.counter;
-        self.counter += 1;
-        if constant_velocity {
-            let photons = m_object.emmit_all_photons();
-            object_tracker.track_photons(photons);
+    pub fn get_update_ratio(&self) -> f64 {
+        self.update_ratio
+    }
+
+    /// Like `register_object_with_update_ratio`, storing `data` next to the object.
+    pub fn register_object_with_data(&mut self, initial_pos: MVector<f64, V>, initial_vel: V, constant_velocity: bool, radius: f64, update_ratio: f64, data: T) -> ObjectId{
+        let m_object = MObject::new(initial_pos, initial_vel, constant_velocity, radius, update_ratio);
+        let mut object_tracker = ObjectTracker::from_history(&m_object, self.photon_queue_budget);
+        object_tracker.create_at(initial_pos);
+        self.insert_object(m_object, object_tracker, data)
+    }
+
+    /// Like `spawn_projectile`, storing `data` next to the projectile. The projectile does not
+    /// collide with its shooter until they have separated.
+    pub fn spawn_projectile_with_data(&mut self, shooter: &ObjectId, launch_velocity: V, constant_velocity: bool, radius: f64, data: T) -> Option<ObjectId>{
+        let (shooter_object, _, _) = self.registered_objects.get(shooter)?;
+        let event = *shooter_object.get_m_pos();
+        let velocity = shooter_object.velocity_from_rest_frame(launch_velocity);
+        let id = self.register_object_with_data(event, velocity, constant_velocity, radius, self.update_ratio, data);
+        self.contacts.insert((id.min(*shooter), id.max(*shooter)));
+        Some(id)
+    }
+
+    fn insert_object(&mut self, m_object: MObject<V>, object_tracker: ObjectTracker<V>, data: T) -> ObjectId{
+        let id = self.ids.allocate();
+        for (observer_id, trackers) in self.observers.iter_mut() {
+            if *observer_id != id {
+                trackers.insert(id, object_tracker.for_receiver(&m_object));
+            }
         }
-        self.registered_objects.insert(id, (m_object, object_tracker));
+        self.registered_objects.insert(id, (m_object, object_tracker, data));
         id
     }
 
-    pub fn unregister_object(&mut self, id: &usize) {
-        self.registered_objects.remove(id);
+    /// Destroys the object at its current event and returns its payload. `id` becomes stale at
+    /// once, but observers keep seeing the object until light from the destruction reaches
+    /// them, see `get_destroyed_object`.
+    pub fn unregister_object(&mut self, id: &ObjectId) -> Option<T> {
+        let event = *self.registered_objects.get(id)?.0.get_m_pos();
+        self.destroy_at_event(id, event)
     }
 
-    pub fn get_object_with_properties(&self, id: &usize) -> Option<&(MObject, ObjectTracker)>{
+    /// Like `unregister_object`, with the destruction at `event`, e.g. an earlier contact.
+    pub(crate) fn destroy_at_event(&mut self, id: &ObjectId, event: MVector<f64, V>) -> Option<T> {
+        let (object, mut tracker, data) = self.registered_objects.remove(id)?;
+        self.ids.release(id);
+        self.remove_observer(id);
+        self.contacts.retain(|(first, second)| first != id && second != id);
+        tracker.destroy_at(event);
+        self.observers.values_mut()
+            .filter_map(|trackers| trackers.get_mut(id))
+            .for_each(|tracker| tracker.destroy_at(event));
+        self.destroyed_objects.insert(*id, (object, tracker));
+        Some(data)
+    }
+
+    /// Unregistered object whose destruction is not yet known to every observer, with what the
+    /// frame object sees of it.
+    pub fn get_destroyed_object(&self, id: &ObjectId) -> Option<&(MObject<V>, ObjectTracker<V>)> {
+        self.destroyed_objects.get(id)
+    }
+
+    pub fn iter_destroyed(&self) -> impl Iterator<Item = (&ObjectId, &MObject<V>, &ObjectTracker<V>)> {
+        self.destroyed_objects.iter().map(|(id, (object, tracker))| (id, object, tracker))
+    }
+
+    /// Makes the registered object `id` the observer and registers the previous observer as a
+    /// regular visible object, swapping their payloads along. Returns the new id of the previous
+    /// observer, or `None` if there is no object `id`.
+    ///
+    /// If `id` was an additional observer its view of the other objects is kept, otherwise it
+    /// starts from the samples the objects keep while their light may still reach it.
+    pub fn switch_observer(&mut self, id: &ObjectId) -> Option<ObjectId>{
+        let (mut object, _, data) = self.registered_objects.remove(id)?;
+        // The observer is steered, so it cannot keep a constant velocity.
+        object.clear_constant_velocity();
+        self.ids.release(id);
+        let mut views = self.observers.remove(id).unwrap_or_default();
+        let view_of_previous = self.observed_frame_object.remove(id);
+        self.contacts.retain(|(first, second)| first != id && second != id);
+        for (object_id, (object, tracker, _)) in self.registered_objects.iter_mut() {
+            *tracker = views.remove(object_id).unwrap_or_else(|| tracker.for_receiver(object));
+        }
+        for (object_id, (object, tracker)) in self.destroyed_objects.iter_mut() {
+            *tracker = views.remove(object_id).unwrap_or_else(|| tracker.for_receiver(object));
+        }
+        let views_of_new: HashMap<ObjectId, ObjectTracker<V>> = self.observers.iter_mut()
+            .filter_map(|(observer_id, trackers)| Some((*observer_id, trackers.remove(id)?)))
+            .collect();
+        let mut views_of_previous = std::mem::replace(&mut self.observed_frame_object, views_of_new);
+
+        let previous = std::mem::replace(&mut self.frame_object, object);
+        let previous_data = std::mem::replace(&mut self.frame_data, data);
+        let previous_tracker = view_of_previous.unwrap_or_else(|| ObjectTracker::from_history(&previous, self.photon_queue_budget));
+        let previous_id = self.insert_object(previous, previous_tracker, previous_data);
+        for (observer_id, trackers) in self.observers.iter_mut() {
+            if let Some(tracker) = views_of_previous.remove(observer_id) {
+                trackers.insert(previous_id, tracker);
+            }
+        }
+
+        let receiver_data = self.receiver_data();
+        self.registered_objects.values_mut()
+            .for_each(|(object, tracker, _)| tracker.recalculate_properties(object, &receiver_data));
+        self.destroyed_objects.values_mut()
+            .for_each(|(object, tracker)| tracker.recalculate_properties(object, &receiver_data));
+        Some(previous_id)
+    }
+
+    /// Makes the registered object `id` an additional observer with its own view of every other
+    /// object, starting from the samples the objects keep while their light may still reach it.
+    /// Returns `false` if there is no such object.
+    pub fn add_observer(&mut self, id: &ObjectId) -> bool {
+        if !self.registered_objects.contains_key(id) {
+            return false
+        }
+        if !self.observers.contains_key(id) {
+            let trackers = self.registered_objects.iter()
+                .filter(|(object_id, _)| *object_id != id)
+                .map(|(object_id, (object, tracker, _))| (*object_id, tracker.for_receiver(object)))
+                .chain(self.destroyed_objects.iter().map(|(object_id, (object, tracker))| (*object_id, tracker.for_receiver(object))))
+                .collect();
+            self.observers.insert(*id, trackers);
+            self.observed_frame_object.insert(*id, ObjectTracker::from_history(&self.frame_object, self.photon_queue_budget));
+        }
+        true
+    }
+
+    pub fn remove_observer(&mut self, id: &ObjectId) {
+        self.observers.remove(id);
+        self.observed_frame_object.remove(id);
+    }
+
+    /// Ids of the registered objects added with `add_observer`.
+    pub fn get_observers(&self) -> impl Iterator<Item = &ObjectId> {
+        self.observers.keys()
+    }
+
+    /// What the observer `observer` sees of the registered object `id`.
+    pub fn get_observed_properties(&self, observer: &ObjectId, id: &ObjectId) -> Option<&ObjectTracker<V>> {
+        self.observers.get(observer)?.get(id)
+    }
+
+    /// What the observer `observer` sees of the frame object.
+    pub fn get_observed_frame_object(&self, observer: &ObjectId) -> Option<&ObjectTracker<V>> {
+        self.observed_frame_object.get(observer)
+    }
+
+    /// Whether `id` refers to a currently registered object. Handles of unregistered objects
+    /// and handles issued by other frames never do.
+    pub fn contains_object(&self, id: &ObjectId) -> bool {
+        self.registered_objects.contains_key(id)
+    }
+
+    pub fn get_object_with_properties(&self, id: &ObjectId) -> Option<&(MObject<V>, ObjectTracker<V>, T)>{
         self.registered_objects.get(id)
     }
 
-    pub fn get_object_mut(&mut self, id: &usize)-> Option<&mut MObject>{
+    pub fn get_object_mut(&mut self, id: &ObjectId)-> Option<&mut MObject<V>>{
         self.registered_objects.get_mut(id).map(|e|&mut e.0)
     }
 
-    pub fn get_frame_object_mut(&mut self)-> &mut MObject{
+    pub fn get_data(&self, id: &ObjectId) -> Option<&T> {
+        self.registered_objects.get(id).map(|e| &e.2)
+    }
+
+    pub fn get_data_mut(&mut self, id: &ObjectId) -> Option<&mut T> {
+        self.registered_objects.get_mut(id).map(|e| &mut e.2)
+    }
+
+    pub fn get_frame_data(&self) -> &T {
+        &self.frame_data
+    }
+
+    pub fn get_frame_data_mut(&mut self) -> &mut T {
+        &mut self.frame_data
+    }
+
+    pub fn len(&self) -> usize {
+        self.registered_objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.registered_objects.is_empty()
+    }
+
+    /// Registered objects with what the frame object sees of them, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&ObjectId, &MObject<V>, &ObjectTracker<V>, &T)> {
+        self.registered_objects.iter().map(|(id, (object, tracker, data))| (id, object, tracker, data))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&ObjectId, &mut MObject<V>, &ObjectTracker<V>, &mut T)> {
+        self.registered_objects.iter_mut().map(|(id, (object, tracker, data))| (id, object, &*tracker, data))
+    }
+
+    /// Registered objects whose light has already reached the frame object.
+    pub fn iter_seen(&self) -> impl Iterator<Item = (&ObjectId, &MObject<V>, &ObjectTracker<V>, &T)> {
+        self.iter().filter(|(_, _, tracker, _)| tracker.get_object_was_seen())
+    }
+
+    pub fn par_iter(&self) -> impl ParallelIterator<Item = (&ObjectId, &MObject<V>, &ObjectTracker<V>, &T)> {
+        self.registered_objects.par_iter().map(|(id, (object, tracker, data))| (id, object, tracker, data))
+    }
+
+    pub fn par_iter_mut(&mut self) -> impl ParallelIterator<Item = (&ObjectId, &mut MObject<V>, &ObjectTracker<V>, &mut T)> {
+        self.registered_objects.par_iter_mut().map(|(id, (object, tracker, data))| (id, object, &*tracker, data))
+    }
+
+    /// Registered objects with what the observer `observer` sees of them. Destroyed objects
+    /// are not included, use `get_observed_properties` for them.
+    pub fn iter_observed(&self, observer: &ObjectId) -> impl Iterator<Item = (&ObjectId, &MObject<V>, &ObjectTracker<V>, &T)> {
+        self.observers.get(observer).into_iter()
+            .flat_map(|trackers| trackers.iter())
+            .filter_map(|(id, tracker)| {
+                let (object, _, data) = self.registered_objects.get(id)?;
+                Some((id, object, tracker, data))
+            })
+    }
+
+    pub fn get_frame_object(&self) -> &MObject<V> {
+        &self.frame_object
+    }
+
+    pub fn get_frame_object_mut(&mut self)-> &mut MObject<V>{
         &mut self.frame_object
     }
 
+    pub fn get_collision_response(&self) -> CollisionResponse {
+        self.collision_response
+    }
+
+    pub fn set_collision_response(&mut self, collision_response: CollisionResponse) {
+        self.collision_response = collision_response;
+    }
+
+    pub fn get_collision_detection(&self) -> bool {
+        self.collision_detection
+    }
+
+    /// Turns the search for touching objects on or off, e.g. for worlds that only need what
+    /// observers see. While off no collision events are reported and nothing responds.
+    pub fn set_collision_detection(&mut self, collision_detection: bool) {
+        self.collision_detection = collision_detection;
+        if !collision_detection {
+            self.collision_events.clear();
+        }
+    }
+
+    pub fn get_photon_queue_budget(&self) -> usize {
+        self.photon_queue_budget
+    }
+
+    /// Limits the samples every tracker keeps waiting per emitting point, see
+    /// `ObjectTracker::get_queue_stats`. Applies to existing trackers too.
+    pub fn set_photon_queue_budget(&mut self, photon_queue_budget: usize) {
+        self.photon_queue_budget = photon_queue_budget;
+        self.trackers_mut().for_each(|tracker| tracker.set_queue_budget(photon_queue_budget));
+    }
+
+    /// Queue statistics of all trackers of the frame object and the observers.
+    pub fn get_photon_queue_stats(&self) -> PhotonQueueStats {
+        self.trackers().fold(PhotonQueueStats::default(), |stats, tracker| stats.merge(&tracker.get_queue_stats()))
+    }
+
+    /// Collisions that started during the last `process_time` call, ordered by time.
+    pub fn get_collision_events(&self) -> &[CollisionEvent<V>] {
+        &self.collision_events
+    }
+
+    /// Payloads of objects absorbed by inelastic merges since the last call, with their former
+    /// ids. `CollisionEvent::get_merged_into` tells which object they were merged into.
+    pub fn take_merged_payloads(&mut self) -> Vec<(ObjectId, T)> {
+        std::mem::take(&mut self.merged_payloads)
+    }
+
     pub fn process_time(&mut self, delta: f64){
-        self.frame_object.process_tau(delta);
+        self.remove_destroyed();
+        let step_starts: HashMap<ObjectId, f64> = self.registered_objects.iter()
+            .map(|(id, (object, _, _))| (*id, object.get_tau()))
+            .collect();
+        let frame_photons = self.frame_object.process_tau(delta);
         let target_time = self.frame_object.get_m_pos().time;
-        let receiver_data = Arc::new(ReceiverData{
-            m_pos: *self.frame_object.get_m_pos(),
-            velocity: *self.frame_object.get_velocity()
-        });
-        self.registered_objects
+        let receiver_data = Arc::new(self.receiver_data());
+        let emitted_photons: HashMap<ObjectId, Vec<Photon<V>>> = self.registered_objects
             .par_iter_mut()
-            .for_each(|(_id, (object, tracker))|{
+            .map(|(id, (object, tracker, _))|{
                 let photons = object.process_time(target_time);
-                tracker.track_photons(photons);
-                tracker.recalculate_properties(&object, receiver_data.as_ref(), delta)
+                tracker.track_photons(photons.clone());
+                tracker.recalculate_properties(object, receiver_data.as_ref());
+                (*id, photons)
             })
+            .collect();
+        self.destroyed_objects
+            .par_iter_mut()
+            .for_each(|(_id, (object, tracker))| tracker.recalculate_properties(object, receiver_data.as_ref()));
+        let registered_objects = &self.registered_objects;
+        let destroyed_objects = &self.destroyed_objects;
+        self.observers
+            .par_iter_mut()
+            .for_each(|(observer_id, trackers)|{
+                let Some((observer, _, _)) = registered_objects.get(observer_id) else {
+                    return
+                };
+                let receiver_data = ReceiverData::of(observer);
+                for (id, tracker) in trackers.iter_mut() {
+                    let object = match registered_objects.get(id) {
+                        Some((object, _, _)) => object,
+                        None => match destroyed_objects.get(id) {
+                            Some((object, _)) => object,
+                            None => continue,
+                        },
+                    };
+                    if let Some(photons) = emitted_photons.get(id) {
+                        tracker.track_photons(photons.clone());
+                    }
+                    tracker.recalculate_properties(object, &receiver_data);
+                }
+            });
+        if !self.observed_frame_object.is_empty() {
+            let frame_object = &self.frame_object;
+            self.observed_frame_object
+                .par_iter_mut()
+                .for_each(|(observer_id, tracker)|{
+                    let Some((observer, _, _)) = registered_objects.get(observer_id) else {
+                        return
+                    };
+                    if !frame_photons.is_empty() {
+                        tracker.track_photons(frame_photons.clone());
+                    }
+                    tracker.recalculate_properties(frame_object, &ReceiverData::of(observer));
+                });
+        }
+        if self.collision_detection {
+            self.detect_collisions(&step_starts);
+            self.respond_to_collisions(target_time);
+        }
+        self.forget_seen_samples();
+    }
+
+    /// Lets every object forget samples whose light has passed all objects that may become
+    /// observers.
+    fn forget_seen_samples(&mut self){
+        let receivers: Vec<ReceiverData<V>> = std::iter::once(&self.frame_object)
+            .chain(self.registered_objects.values().map(|(object, _, _)| object))
+            .map(ReceiverData::of)
+            .collect();
+        let budget = self.photon_queue_budget;
+        self.frame_object.forget_samples_seen_by(&receivers, budget);
+        self.registered_objects
+            .par_iter_mut()
+            .for_each(|(_, (object, _, _))| object.forget_samples_seen_by(&receivers, budget));
+        self.destroyed_objects
+            .par_iter_mut()
+            .for_each(|(_, (object, _))| object.forget_samples_seen_by(&receivers, budget));
+    }
+
+    /// Forgets destroyed objects once every observer has been shown their destruction.
+    fn remove_destroyed(&mut self){
+        self.observers.values_mut()
+            .for_each(|trackers| trackers.retain(|_, tracker| !tracker.get_object_was_destroyed()));
+        let observers = &self.observers;
+        self.destroyed_objects.retain(|id, (_, tracker)| {
+            !tracker.get_object_was_destroyed() || observers.values().any(|trackers| trackers.contains_key(id))
+        });
+    }
+
+    fn receiver_data(&self) -> ReceiverData<V>{
+        ReceiverData::of(&self.frame_object)
+    }
+
+    fn trackers(&self) -> impl Iterator<Item = &ObjectTracker<V>>{
+        self.registered_objects.values().map(|(_, tracker, _)| tracker)
+            .chain(self.destroyed_objects.values().map(|(_, tracker)| tracker))
+            .chain(self.observers.values().flat_map(HashMap::values))
+            .chain(self.observed_frame_object.values())
     }
-}
\ No newline at end of file
+
+    fn trackers_mut(&mut self) -> impl Iterator<Item = &mut ObjectTracker<V>>{
+        self.registered_objects.values_mut().map(|(_, tracker, _)| tracker)
+            .chain(self.destroyed_objects.values_mut().map(|(_, tracker)| tracker))
+            .chain(self.observers.values_mut().flat_map(HashMap::values_mut))
+            .chain(self.observed_frame_object.values_mut())
+    }
+
+    fn detect_collisions(&mut self, step_starts: &HashMap<ObjectId, f64>){
+        self.collision_events.clear();
+        let mut steps: Vec<(ObjectId, WorldlineStep<V>)> = self.registered_objects.iter()
+            .filter_map(|(id, (object, _, _))| Some((*id, WorldlineStep::new(
+                *object.get_motion_segment(),
+                *step_starts.get(id)?,
+                object.get_tau(),
+                object.get_radius(),
+            ))))
+            .collect();
+        // Sweep along the first axis, so that pairs too far apart there are never compared.
+        steps.sort_by(|(_, a), (_, b)| a.sweep_key().total_cmp(&b.sweep_key()));
+        let reach = WorldlineStep::sweep_reach(steps.iter().map(|(_, step)| step));
+        for (i, (a_id, a)) in steps.iter().enumerate() {
+            for (b_id, b) in &steps[i + 1..] {
+                if b.sweep_key() - a.sweep_key() > reach {
+                    break
+                }
+                let ((first, a), (second, b)) = if a_id < b_id { ((*a_id, a), (*b_id, b)) } else { ((*b_id, b), (*a_id, a)) };
+                if !a.may_touch(b) {
+                    continue
+                }
+                let Some(contact) = detect_contact(a, b) else {
+                    continue
+                };
+                let pair = (first, second);
+                if !self.contacts.contains(&pair) && let Some(event) = contact.first_contact {
+                    self.collision_events.push(CollisionEvent::new(&event, first, second));
+                }
+                if contact.touching_at_end {
+                    self.contacts.insert(pair);
+                } else {
+                    self.contacts.remove(&pair);
+                }
+            }
+        }
+        // Pairs skipped above are apart at the end of the step.
+        let steps: HashMap<ObjectId, &WorldlineStep<V>> = steps.iter().map(|(id, step)| (*id, step)).collect();
+        self.contacts.retain(|(first, second)| match (steps.get(first), steps.get(second)) {
+            (Some(a), Some(b)) => a.may_touch(b),
+            _ => true,
+        });
+        self.collision_events.sort_by(|a, b| a.get_event().time.total_cmp(&b.get_event().time)
+            .then((a.get_first(), a.get_second()).cmp(&(b.get_first(), b.get_second()))));
+    }
+
+    /// Applies `collision_response` to the collisions of the last step, with the momenta the
+    /// objects had at the contact. New velocities take effect from the contact on.
+    fn respond_to_collisions(&mut self, target_time: f64){
+        if self.collision_response == CollisionResponse::None {
+            return
+        }
+        let mut events = std::mem::take(&mut self.collision_events);
+        for event in events.iter_mut() {
+            let (first, second) = (event.get_first(), event.get_second());
+            let (Some((first_object, _, _)), Some((second_object, _, _))) = (self.registered_objects.get(&first), self.registered_objects.get(&second)) else {
+                continue
+            };
+            let first_tau = first_object.covered_tau_at_time(event.get_first_center().time);
+            let second_tau = second_object.covered_tau_at_time(event.get_second_center().time);
+            let (first_momentum, second_momentum) = (first_object.four_momentum_at_tau(first_tau), second_object.four_momentum_at_tau(second_tau));
+            match self.collision_response {
+                CollisionResponse::None => {}
+                CollisionResponse::Elastic => {
+                    if let Some((first_after, second_after)) = elastic_response(first_momentum, second_momentum, *event.get_first_center(), *event.get_second_center()) {
+                        self.change_velocity_at(&first, first_tau, first_after.velocity(), target_time);
+                        self.change_velocity_at(&second, second_tau, second_after.velocity(), target_time);
+                    }
+                }
+                CollisionResponse::Inelastic => {
+                    let total = first_momentum + second_momentum;
+                    let constant_velocity = first_object.constant_velocity() && second_object.constant_velocity();
+                    let radius = merged_radius::<V>(first_object.get_radius(), second_object.get_radius());
+                    let spectrum = first_object.get_spectrum().clone();
+                    let luminosity = first_object.get_luminosity() + second_object.get_luminosity();
+                    let update_ratio = first_object.get_update_ratio();
+                    let Some(data) = self.destroy_at_event(&first, *event.get_event()) else {
+                        continue
+                    };
+                    if let Some(second_data) = self.destroy_at_event(&second, *event.get_event()) {
+                        self.merged_payloads.push((second, second_data));
+                    }
+                    let id = self.register_object_with_data(*event.get_event(), total.velocity(), constant_velocity, radius, update_ratio, data);
+                    if let Some((object, tracker, _)) = self.registered_objects.get_mut(&id) {
+                        object.set_rest_mass(total.mass());
+                        object.set_spectrum(spectrum);
+                        object.set_luminosity(luminosity);
+                        let photons = object.process_time(target_time);
+                        tracker.track_photons(photons.clone());
+                        self.track_photons_by_observers(&id, photons);
+                    }
+                    event.set_merged_into(id);
+                }
+            }
+        }
+        self.collision_events = events;
+    }
+
+    fn change_velocity_at(&mut self, id: &ObjectId, tau: f64, velocity: V, target_time: f64){
+        if let Some((object, tracker, _)) = self.registered_objects.get_mut(id) {
+            let photons = object.change_velocity_at(tau, velocity, target_time);
+            tracker.track_photons(photons.clone());
+            self.track_photons_by_observers(id, photons);
+        }
+    }
+
+    fn track_photons_by_observers(&mut self, id: &ObjectId, photons: Vec<Photon<V>>){
+        self.observers.values_mut()
+            .filter_map(|trackers| trackers.get_mut(id))
+            .for_each(|tracker| tracker.track_photons(photons.clone()));
+    }
+}
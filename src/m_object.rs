@@ -1,241 +1,389 @@
+use std::collections::VecDeque;
 use vector2d::Vector2D;
+use crate::four_momentum::FourMomentum;
 use crate::m_vector::MVector;
+use crate::motion::MotionSegment;
+use crate::object_tracker::{thin_queue, ReceiverData};
 use crate::photon::{Photon, PhotonEmittingPosition};
-use crate::{MAX_SAFE_SPEED, UPDATE_RATIO};
+use crate::spatial_vector::SpatialVector;
+use crate::spectrum::Spectrum;
+use crate::velocity;
+use crate::worldline::{Worldline, WorldlinePoint};
+use crate::DEFAULT_UPDATE_RATIO;
 
-pub struct MObject{
+pub struct MObject<V = Vector2D<f64>>{
 
     constant_velocity: bool,
     radius: f64,
+    update_ratio: f64,
+    rest_mass: f64,
+    spectrum: Spectrum,
+    spectrum_revision: u64,
+    luminosity: f64,
 
     tau: f64,
-    m_pos: MVector<f64>,
-    velocity: Vector2D<f64>,
-    acceleration: Vector2D<f64>,
+    m_pos: MVector<f64, V>,
+    velocity: V,
+    acceleration: V,
 
-    t_from_last_update_in_base_frame: f64,
+    segment: MotionSegment<V>,
+    history: Option<Worldline<V>>,
     constant_gamma: f64,
-    constant_between_photons_vector: MVector<f64>,
 
-    front_offset: MVector<f64>,
-    back_offset: MVector<f64>,
-    bottom_offset: MVector<f64>,
-    top_offset: MVector<f64>,
+    outline: Vec<V>,
+    pending_photons: Vec<Photon<V>>,
+    /// Sample sets emitted on every change of motion, oldest first, as long as their light may
+    /// still reach a receiver.
+    emitted_samples: VecDeque<Vec<Photon<V>>>,
 
 }
 
-impl Default for MObject{
+impl<V: SpatialVector> Default for MObject<V>{
     fn default() -> Self {
-        Self::new(MVector::default(), Vector2D::default(), false, 0.0)
+        Self::new(MVector::default(), V::default(), false, 0.0, DEFAULT_UPDATE_RATIO)
     }
 }
 
-impl MObject{
-    pub(crate) fn new(initial_pos: MVector<f64>, initial_vel: Vector2D<f64>, constant_velocity: bool, radius: f64) -> Self{
+impl<V: SpatialVector> MObject<V>{
+    pub(crate) fn new(initial_pos: MVector<f64, V>, initial_vel: V, constant_velocity: bool, radius: f64, update_ratio: f64) -> Self{
         let mut res = Self{
             constant_velocity,
             radius,
+            update_ratio,
+            rest_mass: 1.0,
+            spectrum: Spectrum::default(),
+            spectrum_revision: 0,
+            luminosity: 1.0,
             tau: 0.0,
             m_pos: initial_pos,
             velocity: initial_vel,
             acceleration: Default::default(),
-            t_from_last_update_in_base_frame: 0.0,
+            segment: MotionSegment::new(initial_pos, 0.0, initial_vel, Default::default()),
+            history: None,
 
             constant_gamma: 0.0,
-            constant_between_photons_vector: Default::default(),
 
-            front_offset: Default::default(),
-            back_offset: Default::default(),
-            bottom_offset: Default::default(),
-            top_offset: Default::default(),
+            outline: Self::default_outline(radius),
+            pending_photons: vec![],
+            emitted_samples: Default::default(),
         };
         if constant_velocity {
             res.ready_constant_v()
         }
-        res.update_offsets();
+        let photons = res.emmit_all_photons();
+        res.emitted_samples.push_back(photons);
         res
     }
 
-    pub(crate) fn process_tau(&mut self, tau: f64){
-        let mut gamma = self.gamma();
-        let mut rest_tau = tau;
-        let mut update_ratio_in_base_frame = UPDATE_RATIO * gamma;
-        while rest_tau > UPDATE_RATIO {
-            rest_tau -= UPDATE_RATIO;
-            self.m_pos = self.m_pos + MVector::new(update_ratio_in_base_frame, self.velocity * update_ratio_in_base_frame);
-            if self.acceleration.length() > 0.0 {
-                self.accelerate(UPDATE_RATIO);
-                gamma = self.gamma();
-            }
-            update_ratio_in_base_frame = UPDATE_RATIO * gamma;
-        }
-        update_ratio_in_base_frame = rest_tau * gamma;
-        self.m_pos = self.m_pos + MVector::new(update_ratio_in_base_frame, self.velocity * update_ratio_in_base_frame);
-        if self.acceleration.length() > 0.0 {
-            self.accelerate(rest_tau);
-        }
-        self.tau += tau;
+    /// Advances by `tau` of proper time. Returns the samples emitted since the last call, one
+    /// set per change of motion, as receivers trace the segments in between exactly.
+    pub(crate) fn process_tau(&mut self, tau: f64) -> Vec<Photon<V>>{
+        let target_tau = self.tau + tau;
+        self.move_to_tau(target_tau);
+        std::mem::take(&mut self.pending_photons)
     }
 
-    pub(crate) fn process_time(&mut self, target_time: f64) -> Vec<Photon>{
+    /// Like `process_tau`, advancing to coordinate time `target_time`.
+    pub(crate) fn process_time(&mut self, target_time: f64) -> Vec<Photon<V>>{
+        let res = std::mem::take(&mut self.pending_photons);
         let delta = target_time - self.m_pos.time;
         if delta < 0.0{
-            return vec![]
+            return res
         }
-        let mut gamma = self.gamma();
         if self.constant_velocity {
-            self.tau += delta / gamma;
+            self.tau += delta / self.gamma();
             self.m_pos = self.m_pos + MVector::new(delta, self.velocity * delta);
-            vec![]
         } else {
-            let mut res = vec![];
-            let mut update_ratio_in_base_frame = UPDATE_RATIO * gamma;
-            self.t_from_last_update_in_base_frame += delta;
-            while self.check_for_next_update(update_ratio_in_base_frame) {
-                self.m_pos = self.m_pos + MVector::new(update_ratio_in_base_frame, self.velocity * update_ratio_in_base_frame);
-                if self.acceleration.length() > 0.0 {
-                    self.accelerate(UPDATE_RATIO);
-                    gamma = self.gamma();
-                }
-                self.tau += UPDATE_RATIO;
-                update_ratio_in_base_frame = UPDATE_RATIO * gamma;
-                res.append(&mut self.emmit_all_photons())
-            }
-            res
+            self.move_to_tau(self.segment.tau_at_time(target_time));
         }
+        res
     }
 
     pub fn gamma(&self) -> f64{
         if self.constant_velocity {
             return self.constant_gamma
         }
-        1.0/(1.0 - self.velocity.length_squared()).sqrt()
+        velocity::gamma(self.velocity)
     }
 
-    pub fn one_over_gamma(&self) -> f64{
-        if self.constant_velocity {
-            return 1.0/self.constant_gamma
-        }
-        (1.0 - self.velocity.length_squared()).sqrt()
+    pub fn constant_velocity(&self) -> bool {
+        self.constant_velocity
     }
 
-    pub fn calculate_between_photons_vector(&self) -> MVector<f64>{
-        let gamma = self.gamma();
-        let dt = UPDATE_RATIO * gamma;
-        let dx = self.velocity * dt;
-        MVector::new(dt, dx)
+    /// Lets `set_velocity` and `set_acceleration` change the motion again.
+    pub(crate) fn clear_constant_velocity(&mut self) {
+        self.constant_velocity = false;
     }
 
-    pub fn constant_velocity(&self) -> bool {
-        self.constant_velocity
+    /// Proper time between two ticks of the object's signal, e.g. the flashes of a beacon.
+    /// Receivers see the ticks at `ObjectTracker::get_observed_period`.
+    pub fn get_update_ratio(&self) -> f64 {
+        self.update_ratio
+    }
+
+    /// Changes the proper time between ticks, e.g. the period of a beacon. Non-positive
+    /// values are ignored.
+    pub fn set_update_ratio(&mut self, update_ratio: f64) {
+        if update_ratio <= 0.0 {
+            return
+        }
+        self.update_ratio = update_ratio;
     }
 
     pub fn get_radius(&self) -> f64 {
         self.radius
     }
 
+    pub fn get_rest_mass(&self) -> f64 {
+        self.rest_mass
+    }
+
+    pub fn set_rest_mass(&mut self, rest_mass: f64) {
+        self.rest_mass = rest_mass;
+    }
+
+    /// Emission spectrum in the rest frame.
+    pub fn get_spectrum(&self) -> &Spectrum {
+        &self.spectrum
+    }
+
+    pub fn set_spectrum(&mut self, spectrum: Spectrum) {
+        self.spectrum = spectrum;
+        self.spectrum_revision += 1;
+    }
+
+    /// Changes whenever the spectrum is replaced, so derived colors know when to recompute.
+    pub(crate) fn get_spectrum_revision(&self) -> u64 {
+        self.spectrum_revision
+    }
+
+    /// Power emitted in the rest frame, evenly in all directions.
+    pub fn get_luminosity(&self) -> f64 {
+        self.luminosity
+    }
+
+    pub fn set_luminosity(&mut self, luminosity: f64) {
+        self.luminosity = luminosity;
+    }
+
+    pub fn four_momentum(&self) -> FourMomentum<V> {
+        FourMomentum::from_mass_and_velocity(self.rest_mass, self.velocity)
+    }
+
+    /// Four-momentum measured by an observer moving with `frame_velocity`.
+    pub fn four_momentum_in_frame(&self, frame_velocity: V) -> FourMomentum<V> {
+        self.four_momentum().lorentz_transform(frame_velocity)
+    }
+
+    pub fn energy(&self) -> f64 {
+        self.four_momentum().energy()
+    }
+
+    pub fn momentum(&self) -> V {
+        self.four_momentum().momentum()
+    }
+
+    pub fn kinetic_energy(&self) -> f64 {
+        self.four_momentum().kinetic_energy()
+    }
+
+    /// Starts recording the worldline, keeping at most `capacity` motion segments.
+    pub fn enable_history(&mut self, capacity: usize) {
+        let mut history = Worldline::new(capacity);
+        history.push(self.segment);
+        self.history = Some(history);
+    }
+
+    pub fn disable_history(&mut self) {
+        self.history = None;
+    }
+
+    pub fn get_history(&self) -> Option<&Worldline<V>> {
+        self.history.as_ref()
+    }
+
+    /// Recorded state at coordinate time `time`, if it is covered by the history.
+    pub fn history_at_time(&self, time: f64) -> Option<WorldlinePoint<V>> {
+        if time > self.m_pos.time {
+            return None
+        }
+        self.history.as_ref()?.state_at_time(time)
+    }
+
+    /// Recorded state at proper time `tau`, if it is covered by the history.
+    pub fn history_at_tau(&self, tau: f64) -> Option<WorldlinePoint<V>> {
+        if tau > self.tau {
+            return None
+        }
+        self.history.as_ref()?.state_at_tau(tau)
+    }
+
+    /// Recorded state simultaneous with `observer_event` for an observer moving with `observer_velocity`.
+    pub fn history_simultaneous_with(&self, observer_event: &MVector<f64, V>, observer_velocity: V) -> Option<WorldlinePoint<V>> {
+        let point = self.history.as_ref()?.state_simultaneous_with(observer_event, observer_velocity)?;
+        if point.get_tau() > self.tau {
+            return None
+        }
+        Some(point)
+    }
+
     pub fn get_tau(&self) -> f64 {
         self.tau
     }
 
-    pub fn get_m_pos(&self) -> &MVector<f64> {
+    pub fn get_m_pos(&self) -> &MVector<f64, V> {
         &self.m_pos
     }
 
-    pub fn get_velocity(&self) -> &Vector2D<f64> {
+    pub fn get_velocity(&self) -> &V {
         &self.velocity
     }
 
-    pub fn get_acceleration(&self) -> &Vector2D<f64> {
+    /// Velocity of this object measured in the rest frame of `observer`.
+    pub fn velocity_relative_to(&self, observer: &MObject<V>) -> V {
+        velocity::relative_velocity(observer.velocity, self.velocity)
+    }
+
+    /// Motion since the last change of velocity or acceleration.
+    pub fn get_motion_segment(&self) -> &MotionSegment<V> {
+        &self.segment
+    }
+
+    /// Base frame velocity of something moving with `velocity` in this object's rest frame.
+    pub fn velocity_from_rest_frame(&self, velocity: V) -> V {
+        velocity::add_velocities(self.velocity, velocity)
+    }
+
+    pub fn get_acceleration(&self) -> &V {
         &self.acceleration
     }
 
-    pub fn set_velocity(&mut self, velocity: Vector2D<f64>) {
+    pub fn set_velocity(&mut self, velocity: V) {
         if self.constant_velocity {
             return;
         }
-        self.update_offsets();
         self.velocity = velocity;
+        self.restart_segment();
     }
 
-    pub fn set_acceleration(&mut self, acceleration: Vector2D<f64>) {
+    pub fn set_acceleration(&mut self, acceleration: V) {
         if self.constant_velocity {
             return;
         }
         self.acceleration = acceleration;
+        self.restart_segment();
+    }
+
+    /// Velocity change caused by the frame itself at proper time `tau`, e.g. at a contact
+    /// during the last step, after which the object moves on to `target_time`. Unlike
+    /// `set_velocity` it also applies to constant-velocity objects, which become regular ones.
+    pub(crate) fn change_velocity_at(&mut self, tau: f64, velocity: V, target_time: f64) -> Vec<Photon<V>> {
+        self.move_to_tau(tau.clamp(self.segment.get_start_tau(), self.tau));
+        self.constant_velocity = false;
+        self.velocity = velocity;
+        self.restart_segment();
+        self.process_time(target_time)
+    }
+
+    /// Proper time at coordinate time `time`, limited to the current motion segment up to now.
+    pub(crate) fn covered_tau_at_time(&self, time: f64) -> f64 {
+        self.segment.tau_at_time(time).clamp(self.segment.get_start_tau(), self.tau)
+    }
+
+    /// Four-momentum at proper time `tau` of the current motion segment.
+    pub(crate) fn four_momentum_at_tau(&self, tau: f64) -> FourMomentum<V> {
+        FourMomentum::from_mass_and_velocity(self.rest_mass, self.segment.state_at_tau(tau).1)
+    }
+
+    /// Emission points relative to the center, in the rest frame. By default one point at
+    /// `radius` on each side of every axis.
+    pub fn get_outline(&self) -> &[V] {
+        &self.outline
+    }
+
+    /// Replaces the emission points, e.g. with the corners of a polygon. Receivers keep seeing
+    /// the previous outline until light emitted from now on reaches them.
+    pub fn set_outline(&mut self, outline: Vec<V>) {
+        self.outline = outline;
+        self.continue_segment();
+    }
+
+    /// Samples the worldline of the center and every outline point at the current proper time.
+    pub(crate) fn emmit_all_photons(&mut self) -> Vec<Photon<V>> {
+        self.photons_for(self.segment.split_at(self.tau))
     }
 
-    pub(crate) fn emmit_all_photons(&mut self) -> Vec<Photon> {
-        let mut res = vec![Photon::new(self.m_pos, PhotonEmittingPosition::CENTER)];
-        if self.radius > 0.0 {
-            res.reserve(4);
-            res.push(Photon::new(self.m_pos + self.front_offset, PhotonEmittingPosition::FRONT));
-            res.push(Photon::new(self.m_pos + self.back_offset, PhotonEmittingPosition::BACK));
-            res.push(Photon::new(self.m_pos + self.bottom_offset, PhotonEmittingPosition::BOTTOM));
-            res.push(Photon::new(self.m_pos + self.top_offset, PhotonEmittingPosition::TOP));
+    /// Every sample still kept, oldest first, to start trackers for new receivers from.
+    pub(crate) fn get_emitted_samples(&self) -> impl Iterator<Item = &Photon<V>> {
+        self.emitted_samples.iter().flatten()
+    }
+
+    /// Forgets sample sets once every one of `receivers` has seen the next one, as their light
+    /// cannot reach them any more. Receivers added later only see what is kept for these. Then
+    /// thins the rest down to `budget` sets like a tracker queue.
+    pub(crate) fn forget_samples_seen_by(&mut self, receivers: &[ReceiverData<V>], budget: usize) {
+        while self.emitted_samples.len() > 1 && self.emitted_samples[1].iter()
+            .all(|photon| receivers.iter().all(|receiver| receiver.has_seen(&photon.get_emmit_pos()))) {
+            self.emitted_samples.pop_front();
+        }
+        let budget = budget.max(2);
+        if self.emitted_samples.len() > budget {
+            thin_queue(&mut self.emitted_samples, budget - budget / 4, |photons| &photons[0]);
         }
-        res
     }
+
 }
 
-impl MObject{
+impl<V: SpatialVector> MObject<V>{
 
-    fn check_for_next_update(&mut self, update_ratio_in_base_frame: f64) -> bool{
-        if self.t_from_last_update_in_base_frame > update_ratio_in_base_frame{
-            self.t_from_last_update_in_base_frame -= update_ratio_in_base_frame;
-            return true
+    fn move_to_tau(&mut self, tau: f64){
+        self.tau = tau;
+        if self.acceleration.length() > 0.0 {
+            (self.m_pos, self.velocity) = self.segment.state_at_tau(tau);
+        } else {
+            self.m_pos = self.segment.state_at_tau(tau).0;
         }
-        false
     }
-    fn ready_constant_v(&mut self) {
-        self.constant_gamma = 1.0/(1.0 - self.velocity.length_squared()).sqrt();
-        self.constant_between_photons_vector = self.calculate_between_photons_vector();
-    }
-    fn update_offsets(&mut self){
-        if self.radius > 0.0 {
-            let gamma = self.gamma();
-            let gamma_v = self.gamma() * self.velocity.length();
-            let v_direction = match self.velocity.length_squared() {
-                x if x < 0.001 => Vector2D::new(1.0, 0.0),
-                _ => self.velocity.normalise()
-            };
-            self.front_offset = Self::offset_for_vec(gamma, gamma_v, v_direction, Vector2D::new(self.radius, 0.0));
-            self.back_offset = Self::offset_for_vec(gamma, gamma_v, v_direction, Vector2D::new(-self.radius, 0.0));
-            self.bottom_offset = Self::offset_for_vec(gamma, gamma_v, v_direction, Vector2D::new(0.0, -self.radius));
-            self.top_offset = Self::offset_for_vec(gamma, gamma_v, v_direction, Vector2D::new(0.0, self.radius));
-        }
+
+    fn restart_segment(&mut self){
+        self.start_segment(MotionSegment::new(self.m_pos, self.tau, self.velocity, self.acceleration));
     }
 
-    fn offset_for_vec(gamma: f64, gamma_v: f64, v_direction: Vector2D<f64>, vec: Vector2D<f64>) -> MVector<f64>{
-        let parallel_part = Vector2D::dot(v_direction, vec);
-        let pos_parallel = v_direction * parallel_part;
-        let pos_perp = vec - pos_parallel;
-        let pos_parallel_prime = pos_parallel * gamma;
-        let pos_prime = pos_perp + pos_parallel_prime;
-        let t_prime = gamma_v * parallel_part;
-        MVector::new(t_prime, pos_prime)
+    /// Starts a new segment without changing the motion, e.g. to emit samples of a new outline.
+    fn continue_segment(&mut self){
+        self.start_segment(self.segment.split_at(self.tau));
     }
 
-    fn accelerate(&mut self, dt: f64){
-        let dv = self.acceleration * dt;
-        let speed = self.velocity.length();
-        if speed == 0.0 {
-            self.velocity = dv;
-            return;
+    fn start_segment(&mut self, segment: MotionSegment<V>){
+        self.segment = segment;
+        if let Some(history) = self.history.as_mut() {
+            history.push(self.segment);
         }
-        let current_v_direction = self.velocity.normalise();
-        let dvx = Vector2D::dot(current_v_direction, dv);
-        let dvy_vec = dv - current_v_direction * dvx;
-        let dvy = dvy_vec.length();
-        let one_over_gamma = self.one_over_gamma();
-        let new_vx = (speed + dvx) / (1.0 + speed * dvx);
-        let new_vy = one_over_gamma * dvy / (1.0 + speed * dvx);
-        let new_v = current_v_direction * new_vx + dvy_vec.normalise() * new_vy;
-        self.velocity = new_v;
-        if self.velocity.length_squared() >= 1.0 {
-            self.velocity = self.velocity.normalise() * MAX_SAFE_SPEED
+        let photons = self.emmit_all_photons();
+        self.pending_photons.extend(photons.iter().cloned());
+        self.emitted_samples.push_back(photons);
+    }
+
+    fn ready_constant_v(&mut self) {
+        self.constant_gamma = velocity::gamma(self.velocity);
+    }
+
+    fn default_outline(radius: f64) -> Vec<V>{
+        if radius <= 0.0 {
+            return vec![]
         }
-        self.update_offsets();
+        (0..V::DIM)
+            .flat_map(|axis| [V::axis(axis) * radius, V::axis(axis) * -radius])
+            .collect()
+    }
+
+    /// Outline points move rigidly with the center, so their segments stay exact while it accelerates.
+    fn photons_for(&self, segment: MotionSegment<V>) -> Vec<Photon<V>>{
+        let size = self.outline.len();
+        let mut res = vec![Photon::new(segment, PhotonEmittingPosition::CENTER, V::zero(), size)];
+        res.extend(self.outline.iter().enumerate()
+            .map(|(i, point)| Photon::new(segment.rigid_point(*point), PhotonEmittingPosition::OUTLINE(i), *point, size)));
+        res
     }
 
 }
\ No newline at end of file
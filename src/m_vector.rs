@@ -1,13 +1,16 @@
 use std::ops::{Add, Div, Mul, Sub};
 use vector2d::Vector2D;
+use crate::spatial_vector::SpatialVector;
+use crate::vector3d::Vector3D;
+use crate::velocity::gamma;
 
 #[derive(Copy, Clone, Default, Debug)]
-pub struct MVector<T>{
-    pub pos: Vector2D<T>,
+pub struct MVector<T, V = Vector2D<T>>{
+    pub pos: V,
     pub time: T,
 }
 
-impl<T> Add for MVector<T> where T: Add<T, Output = T> + Copy{
+impl<T, V> Add for MVector<T, V> where T: Add<T, Output = T> + Copy, V: Add<V, Output = V>{
     type Output = Self;
 
     fn add(self, rhs: Self) -> Self::Output {
@@ -18,7 +21,7 @@ impl<T> Add for MVector<T> where T: Add<T, Output = T> + Copy{
     }
 }
 
-impl<T> Sub for MVector<T>  where T: Sub<T, Output=T> + Copy{
+impl<T, V> Sub for MVector<T, V>  where T: Sub<T, Output=T> + Copy, V: Sub<V, Output = V>{
     type Output = Self;
 
     fn sub(self, rhs: Self) -> Self::Output {
@@ -29,8 +32,8 @@ impl<T> Sub for MVector<T>  where T: Sub<T, Output=T> + Copy{
     }
 }
 
-impl<T> Mul<T> for MVector<T> where T: Mul<T, Output=T> + Copy{
-    type Output = MVector<T>;
+impl<T, V> Mul<T> for MVector<T, V> where T: Mul<T, Output=T> + Copy, V: Mul<T, Output = V>{
+    type Output = MVector<T, V>;
     fn mul(self, rhs: T) -> Self::Output {
         Self{
             pos: self.pos * rhs,
@@ -39,8 +42,8 @@ impl<T> Mul<T> for MVector<T> where T: Mul<T, Output=T> + Copy{
     }
 }
 
-impl<T> Div<T> for MVector<T> where T: Div<T, Output=T> + Copy{
-    type Output = MVector<T>;
+impl<T, V> Div<T> for MVector<T, V> where T: Div<T, Output=T> + Copy, V: Div<T, Output = V>{
+    type Output = MVector<T, V>;
     fn div(self, rhs: T) -> Self::Output {
         Self{
             pos: self.pos / rhs,
@@ -49,9 +52,9 @@ impl<T> Div<T> for MVector<T> where T: Div<T, Output=T> + Copy{
     }
 }
 
-impl MVector<f64> {
+impl<V: SpatialVector> MVector<f64, V> {
 
-    pub fn new(time: f64, pos: Vector2D<f64>) -> Self{
+    pub fn new(time: f64, pos: V) -> Self{
         Self{
             pos,
             time,
@@ -83,26 +86,25 @@ impl MVector<f64> {
 
     pub fn zero() -> Self{
         Self{
-            pos: Vector2D::new(0.0, 0.0),
+            pos: V::zero(),
             time: 0.0,
         }
     }
 
-    pub fn lorentz_transform(&self, velocity: Vector2D<f64>) -> Self{
-        let v_length = velocity.length();
-        if v_length == 0.0 {
-            return self.clone()
+    pub fn lorentz_transform(&self, velocity: V) -> Self{
+        if velocity.length_squared() == 0.0 {
+            return *self
         }
-        let gamma = 1.0/(1.0 - v_length * v_length).sqrt();
+        let gamma = gamma(velocity);
 
         let v_direction = velocity.normalise();
 
-        let pos_parallel = v_direction * Vector2D::dot(v_direction, self.pos);
+        let pos_parallel = v_direction * V::dot(v_direction, self.pos);
         let pos_perp = self.pos - pos_parallel;
 
         let pos_parallel_prime = (pos_parallel - velocity * self.time) * gamma;
         let pos_prime = pos_perp + pos_parallel_prime;
-        let t_prime = gamma * (self.time - Vector2D::dot(velocity, self.pos));
+        let t_prime = gamma * (self.time - V::dot(velocity, self.pos));
         Self{
             pos: pos_prime,
             time: t_prime,
@@ -120,4 +122,14 @@ fn lorentz_invariant_interval() {
     let s2_prime = p_prime.length();
 
     assert!((s2 - s2_prime).abs() < 1e-6);
+}
+
+#[test]
+fn lorentz_invariant_interval_3d() {
+    let p = MVector { pos: Vector3D::new(1.0, 2.0, -0.5), time: 3.0 };
+    let v = Vector3D::new(0.6, 0.2, 0.4);
+    let p_prime = p.lorentz_transform(v);
+
+    assert!((p.length_squared() - p_prime.length_squared()).abs() < 1e-9);
+    assert!((p_prime.lorentz_transform(-v).pos - p.pos).length() < 1e-9);
 }
\ No newline at end of file
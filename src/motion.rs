@@ -0,0 +1,217 @@
+use vector2d::Vector2D;
+use crate::m_vector::MVector;
+use crate::spatial_vector::SpatialVector;
+use crate::velocity::{add_velocities, gamma};
+
+/// Piece of a worldline with constant proper acceleration.
+///
+/// `acceleration` is measured in the rest frame the object had at `start`, so
+/// the segment is a hyperbola (a straight line when it is zero) and every state
+/// on it is evaluated in closed form.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct MotionSegment<V = Vector2D<f64>>{
+    start: MVector<f64, V>,
+    start_tau: f64,
+    velocity: V,
+    acceleration: V,
+}
+
+impl<V: SpatialVector> MotionSegment<V>{
+    pub fn new(start: MVector<f64, V>, start_tau: f64, velocity: V, acceleration: V) -> Self{
+        Self{
+            start,
+            start_tau,
+            velocity,
+            acceleration,
+        }
+    }
+
+    pub fn get_start(&self) -> &MVector<f64, V> {
+        &self.start
+    }
+
+    pub fn get_start_tau(&self) -> f64 {
+        self.start_tau
+    }
+
+    pub fn get_start_velocity(&self) -> &V {
+        &self.velocity
+    }
+
+    pub fn get_acceleration(&self) -> &V {
+        &self.acceleration
+    }
+
+    /// Event and velocity of the object at proper time `tau`.
+    pub fn state_at_tau(&self, tau: f64) -> (MVector<f64, V>, V){
+        let s = tau - self.start_tau;
+        let alpha = self.acceleration.length();
+        if alpha == 0.0 {
+            let dt = s * self.start_gamma();
+            return (self.start + MVector::new(dt, self.velocity * dt), self.velocity)
+        }
+        let direction = self.acceleration / alpha;
+        let rapidity = alpha * s;
+        let in_start_frame = MVector::new(rapidity.sinh() / alpha, direction * ((rapidity.cosh() - 1.0) / alpha));
+        let m_pos = self.start + in_start_frame.lorentz_transform(-self.velocity);
+        let velocity = add_velocities(self.velocity, direction * rapidity.tanh());
+        (m_pos, velocity)
+    }
+
+    /// Proper time at which the object reaches coordinate time `time`.
+    pub fn tau_at_time(&self, time: f64) -> f64{
+        self.tau_at_frame_time(time, V::zero())
+    }
+
+    /// Proper time at which the object reaches time `time` of a frame moving with
+    /// `frame_velocity`, i.e. crosses that frame's simultaneity line.
+    pub fn tau_at_frame_time(&self, time: f64, frame_velocity: V) -> f64{
+        let dt = time - self.start.lorentz_transform(frame_velocity).time;
+        let to_frame_time = |d: MVector<f64, V>| d.lorentz_transform(-self.velocity).lorentz_transform(frame_velocity).time;
+        let a = to_frame_time(MVector::new(1.0, V::zero()));
+        let alpha = self.acceleration.length();
+        if alpha == 0.0 {
+            return self.start_tau + dt / a
+        }
+        // dt = a * (sinh(y) + b * (cosh(y) - 1)) / alpha, with y = alpha * s
+        let b = to_frame_time(MVector::new(0.0, self.acceleration / alpha)) / a;
+        let c = alpha * dt / a + b;
+        let r = (1.0 - b * b).sqrt();
+        let y = (c / r).asinh() - b.atanh();
+        self.start_tau + y / alpha
+    }
+
+    /// Segment starting at `tau` that describes the same worldline.
+    pub fn split_at(&self, tau: f64) -> Self{
+        let (m_pos, velocity) = self.state_at_tau(tau);
+        let alpha = self.acceleration.length();
+        if alpha == 0.0 {
+            return Self::new(m_pos, tau, velocity, self.acceleration)
+        }
+        let rapidity = alpha * (tau - self.start_tau);
+        let four_acceleration = MVector::new(rapidity.sinh(), self.acceleration / alpha * rapidity.cosh()) * alpha;
+        let acceleration = four_acceleration.lorentz_transform(-self.velocity).lorentz_transform(velocity).pos;
+        Self::new(m_pos, tau, velocity, acceleration)
+    }
+
+    /// Same motion with every event moved by `offset`.
+    pub fn shifted(&self, offset: MVector<f64, V>) -> Self{
+        Self::new(self.start + offset, self.start_tau, self.velocity, self.acceleration)
+    }
+
+    /// Worldline of the point at `offset` from the start in the start rest frame, moving
+    /// rigidly with this one: the distance stays the same in the momentary rest frame, so
+    /// points ahead in the direction of the acceleration accelerate less. The proper time is
+    /// counted from `start_tau` like here. Points at or behind the horizon, which cannot
+    /// follow rigidly, keep the acceleration.
+    pub fn rigid_point(&self, offset: V) -> Self{
+        let start = self.start + MVector::new(0.0, offset).lorentz_transform(-self.velocity);
+        let alpha = self.acceleration.length();
+        if alpha > 0.0 {
+            let direction = self.acceleration / alpha;
+            let radius = 1.0 / alpha + V::dot(offset, direction);
+            if radius > 0.0 {
+                return Self::new(start, self.start_tau, self.velocity, direction / radius)
+            }
+        }
+        Self::new(start, self.start_tau, self.velocity, self.acceleration)
+    }
+
+    /// Proper time at which the worldline crosses the past light cone of `event`, i.e. when
+    /// light reaching `event` was emitted. `None` if no such light exists.
+    pub fn tau_at_past_light_cone(&self, event: &MVector<f64, V>) -> Option<f64>{
+        let d = (*event - self.start).lorentz_transform(self.velocity);
+        let alpha = self.acceleration.length();
+        if alpha == 0.0 {
+            return Some(self.start_tau + d.time - d.pos.length())
+        }
+        // In the start rest frame the worldline is (sinh(y), n * (cosh(y) - 1)) / alpha, so a
+        // null separation gives p * sinh(y) + q * cosh(y) = k, a quadratic in e^y.
+        let p = alpha * d.time;
+        let q = -(alpha * V::dot(d.pos, self.acceleration / alpha) + 1.0);
+        let k = alpha * alpha * d.length_squared() / 2.0 + q;
+        let roots = if (p + q).abs() < 1e-12 {
+            vec![(q - p) / (2.0 * k)]
+        } else {
+            let discriminant = (k * k + p * p - q * q).max(0.0).sqrt();
+            vec![(k + discriminant) / (p + q), (k - discriminant) / (p + q)]
+        };
+        roots.into_iter()
+            .filter(|e| *e > 0.0 && e.is_finite())
+            .map(f64::ln)
+            .filter(|y| y.sinh() < p)
+            .max_by(f64::total_cmp)
+            .map(|y| self.start_tau + y / alpha)
+    }
+
+    fn start_gamma(&self) -> f64{
+        gamma(self.velocity)
+    }
+}
+
+#[test]
+fn hyperbolic_motion_from_rest() {
+    let segment = MotionSegment::new(MVector::zero(), 0.0, Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 2.0));
+    let (m_pos, velocity) = segment.state_at_tau(1.5);
+
+    assert!((m_pos.time - 3.0f64.sinh() / 2.0).abs() < 1e-9);
+    assert!((m_pos.pos.y - (3.0f64.cosh() - 1.0) / 2.0).abs() < 1e-9);
+    assert!((velocity.y - 3.0f64.tanh()).abs() < 1e-9);
+    assert!((segment.tau_at_time(m_pos.time) - 1.5).abs() < 1e-9);
+}
+
+#[test]
+fn hyperbolic_motion_time_matches_tau() {
+    let segment = MotionSegment::new(MVector::new(1.0, Vector2D::new(2.0, 0.0)), 0.5, Vector2D::new(0.5, 0.3), Vector2D::new(-0.4, 0.9));
+    for tau in [0.5, 0.7, 1.3, 4.0] {
+        let (m_pos, velocity) = segment.state_at_tau(tau);
+        assert!((segment.tau_at_time(m_pos.time) - tau).abs() < 1e-9);
+        assert!(velocity.length() < 1.0);
+    }
+}
+
+#[test]
+fn hyperbolic_motion_frame_time() {
+    let segment = MotionSegment::new(MVector::new(1.0, Vector2D::new(2.0, 0.0)), 0.5, Vector2D::new(0.5, 0.3), Vector2D::new(-0.4, 0.9));
+    let frame_velocity = Vector2D::new(-0.2, 0.7);
+    let (m_pos, _) = segment.state_at_tau(2.0);
+
+    assert!((segment.tau_at_frame_time(m_pos.lorentz_transform(frame_velocity).time, frame_velocity) - 2.0).abs() < 1e-9);
+}
+
+#[test]
+fn hyperbolic_motion_past_light_cone() {
+    let segment = MotionSegment::new(MVector::new(1.0, Vector2D::new(2.0, 0.0)), 0.5, Vector2D::new(0.5, 0.3), Vector2D::new(-0.4, 0.9));
+    for observer in [MVector::new(6.0, Vector2D::new(-1.0, 0.5)), MVector::new(3.0, Vector2D::new(4.0, 4.0))] {
+        let tau = segment.tau_at_past_light_cone(&observer).unwrap_or_default();
+        let (emission, _) = segment.state_at_tau(tau);
+        assert!((observer - emission).length_squared().abs() < 1e-9);
+        assert!(observer.time > emission.time);
+    }
+
+    let split = segment.split_at(1.7);
+    for tau in [1.7, 2.5, 4.0] {
+        let (expected, expected_velocity) = segment.state_at_tau(tau);
+        let (actual, actual_velocity) = split.state_at_tau(tau);
+        assert!((expected.time - actual.time).abs() < 1e-9);
+        assert!((expected.pos - actual.pos).length() < 1e-9);
+        assert!((expected_velocity - actual_velocity).length() < 1e-9);
+    }
+}
+
+#[test]
+fn rigid_point_keeps_its_distance() {
+    let segment = MotionSegment::new(MVector::new(1.0, Vector2D::new(2.0, 0.0)), 0.5, Vector2D::new(0.5, 0.3), Vector2D::new(-0.4, 0.9));
+    let offset = Vector2D::new(0.3, -0.2);
+    let point = segment.rigid_point(offset);
+    for tau in [0.5, 1.7, 4.0] {
+        // The point simultaneous with the center in its rest frame is still at `offset`.
+        let (center, velocity) = segment.state_at_tau(tau);
+        let at_rest_frame_time = point.tau_at_frame_time(center.lorentz_transform(velocity).time, velocity);
+        let (position, point_velocity) = point.state_at_tau(at_rest_frame_time);
+        let distance = (position - center).lorentz_transform(velocity);
+        assert!(distance.time.abs() < 1e-9);
+        assert!((distance.pos.length() - offset.length()).abs() < 1e-9);
+        assert!((point_velocity - velocity).length() < 1e-9);
+    }
+}
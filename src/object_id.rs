@@ -0,0 +1,78 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static NEXT_FRAME_TAG: AtomicU64 = AtomicU64::new(0);
+
+/// Handle of an object registered in an `MFrame`.
+///
+/// Indices are reused after `MFrame::unregister_object`, but every reuse bumps the generation,
+/// so a stale handle never refers to a newer object. Handles also remember the frame that
+/// issued them and never match objects of another frame.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ObjectId{
+    frame: u64,
+    index: usize,
+    generation: u32,
+}
+
+impl ObjectId{
+    pub fn get_index(&self) -> usize {
+        self.index
+    }
+
+    pub fn get_generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+/// Issues `ObjectId`s of one frame.
+#[derive(Debug)]
+pub(crate) struct ObjectIdAllocator{
+    frame: u64,
+    generations: Vec<u32>,
+    free_indices: Vec<usize>,
+}
+
+impl Default for ObjectIdAllocator{
+    fn default() -> Self {
+        Self{
+            frame: NEXT_FRAME_TAG.fetch_add(1, Ordering::Relaxed),
+            generations: vec![],
+            free_indices: vec![],
+        }
+    }
+}
+
+impl ObjectIdAllocator{
+    pub(crate) fn allocate(&mut self) -> ObjectId{
+        let index = self.free_indices.pop().unwrap_or_else(|| {
+            self.generations.push(0);
+            self.generations.len() - 1
+        });
+        ObjectId{
+            frame: self.frame,
+            index,
+            generation: self.generations[index],
+        }
+    }
+
+    /// Makes the index of `id` available again. Stale handles are ignored.
+    pub(crate) fn release(&mut self, id: &ObjectId){
+        if let Some(generation) = self.generations.get_mut(id.index) && *generation == id.generation && id.frame == self.frame {
+            *generation = generation.wrapping_add(1);
+            self.free_indices.push(id.index);
+        }
+    }
+}
+
+#[test]
+fn released_ids_are_not_reissued() {
+    let mut allocator = ObjectIdAllocator::default();
+    let first = allocator.allocate();
+    allocator.release(&first);
+    allocator.release(&first);
+    let second = allocator.allocate();
+    assert_eq!(first.get_index(), second.get_index());
+    assert_ne!(first, second);
+    assert_ne!(allocator.allocate().get_index(), second.get_index());
+    assert_ne!(ObjectIdAllocator::default().allocate(), first);
+}
@@ -2,312 +2,593 @@ use std::collections::{HashMap, VecDeque};
 use vector2d::Vector2D;
 use crate::m_vector::MVector;
 use crate::m_object::MObject;
+use crate::motion::MotionSegment;
 use crate::photon::{Photon, PhotonEmittingPosition};
-use crate::UPDATE_RATIO;
+use crate::spatial_vector::SpatialVector;
+use crate::spectrum::Color;
+use crate::vector3d::Vector3D;
+use crate::velocity;
+use crate::DEFAULT_PHOTON_QUEUE_BUDGET;
+
+/// Relative tolerance below which a queued sample only restates the motion of the previous one.
+const COALESCE_TOLERANCE: f64 = 1e-9;
+
+/// What a receiver knows about the existence of an object.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Visibility{
+    /// Light from the creation event has not arrived yet.
+    #[default]
+    NotYetCreated,
+    Visible,
+    /// Light from the destruction event has arrived.
+    Destroyed,
+}
 
+/// Event on a source worldline whose light reaches the receiver right now.
 #[derive(Clone, Debug, Default)]
-pub struct PhotonCrossing{
-    photon_emmit_pos: MVector<f64>,
-    photon_emmit_pos_in_receiver_frame: MVector<f64>,
-    time_from_catch: f64,
+pub struct PhotonCrossing<V = Vector2D<f64>>{
+    photon_emmit_pos: MVector<f64, V>,
+    photon_emmit_tau: f64,
+    photon_emmit_velocity: V,
+    photon_emmit_pos_in_receiver_frame: MVector<f64, V>,
+    relative_freq: f64,
 }
 
-pub const LAST_PHOTONS_COUNT: usize = 2;
-pub const LAST_RELATIVE_COUNT: usize = 1;
+/// Newest visible sample of one emitting position. Its motion segment is valid until the next
+/// queued sample becomes visible, so the crossing with the receiver's past light cone is
+/// solved on it exactly.
 #[derive(Clone, Debug)]
-pub struct TrackedSource {
-    last_photons: VecDeque<PhotonCrossing>,
-    relative_freq: Option<f64>,
-    constant_velocity_dx: Option<MVector<f64>>,
-    object_radius: f64,
-
-    receiver_current_pos: MVector<f64>,
-    receiver_v: Vector2D<f64>,
-
-    t_between_last_photons: f64,
-    v_source: MVector<f64>,
+pub struct TrackedSource<V = Vector2D<f64>> {
+    segment: MotionSegment<V>,
+    rest_frame_offset: V,
+    crossing: Option<PhotonCrossing<V>>,
 }
 
 #[derive(Copy, Clone, Debug)]
-pub(crate) struct ReceiverData{
-    pub(crate) m_pos: MVector<f64>,
-    pub(crate) velocity: Vector2D<f64>
+pub(crate) struct ReceiverData<V = Vector2D<f64>>{
+    pub(crate) m_pos: MVector<f64, V>,
+    pub(crate) velocity: V
 }
-impl TrackedSource {
-    fn new(first_photon: &Photon, source: &MObject, receiver: &ReceiverData) -> Self{
-        let (constant_velocity_dx, object_radius) = {
-            let mut constant_velocity_dx = None;
-            if source.constant_velocity() {
-                constant_velocity_dx = Some(source.calculate_between_photons_vector());
-            }
-            (constant_velocity_dx, source.get_radius())
-        };
-        let mut res = Self{
-            last_photons: Default::default(),
-            constant_velocity_dx,
-            object_radius,
-            receiver_current_pos: receiver.m_pos,
-            receiver_v: receiver.velocity,
-            t_between_last_photons: 1.0,
-            v_source: Default::default(),
-            relative_freq: None
-        };
-        let first_crossing = res.calculate_photon_crossing(&first_photon);
-        res.last_photons.push_back(first_crossing);
-        res
+impl<V: SpatialVector> ReceiverData<V> {
+    pub(crate) fn of(receiver: &MObject<V>) -> Self{
+        Self{
+            m_pos: *receiver.get_m_pos(),
+            velocity: *receiver.get_velocity()
+        }
+    }
+
+    /// Whether light from `event` has reached the receiver.
+    pub(crate) fn has_seen(&self, event: &MVector<f64, V>) -> bool{
+        self.m_pos.time >= event.time && (self.m_pos - *event).is_time_or_light_like()
     }
+}
 
-    fn calculate_obj_properties(&mut self){
-        if self.last_photons.len() >=2 {
-            let newest = self.last_photons.back().expect("Checked in if");
-            let oldest = self.last_photons.front().expect("Checked in if");
-            self.t_between_last_photons = oldest.time_from_catch - newest.time_from_catch;
-            self.v_source = (newest.photon_emmit_pos - oldest.photon_emmit_pos) / self.t_between_last_photons;
-            self.relative_freq = Some(UPDATE_RATIO * (self.last_photons.len() - 1) as f64 / self.t_between_last_photons);
+impl<V: SpatialVector> TrackedSource<V> {
+    fn new(first_photon: &Photon<V>) -> Self{
+        Self{
+            segment: *first_photon.get_segment(),
+            rest_frame_offset: *first_photon.get_rest_frame_offset(),
+            crossing: None,
         }
     }
 
-    fn relative_position(&self) -> Option<Vector2D<f64>>{
-        let current_m_vector = self.current_m_vector()?;
-        let emmit_minus_curr = current_m_vector - self.receiver_current_pos;
-        let vec = emmit_minus_curr.lorentz_transform(self.receiver_v);
-        Some(vec.pos)
+    fn insert_into(&mut self, photon: &Photon<V>){
+        self.segment = *photon.get_segment();
+        self.rest_frame_offset = *photon.get_rest_frame_offset();
     }
 
-    fn current_m_vector(&self) -> Option<MVector<f64>> {
-        if self.last_photons.len() >= 1 {
-            let newest = self.last_photons.back().expect("Checked in if");
-            let current_m_vector = newest.photon_emmit_pos + self.v_source * newest.time_from_catch;
-            return Some(current_m_vector)
-        }
-        None
+    fn relative_position(&self) -> Option<V>{
+        Some(self.crossing.as_ref()?.photon_emmit_pos_in_receiver_frame.pos)
     }
 
-    fn relative_frequency(&self) -> Option<f64>{
-        self.relative_freq
+    fn recalculate(&mut self, receiver: &ReceiverData<V>){
+        self.crossing = self.calculate_photon_crossing(receiver);
     }
 
-    fn insert_into(&mut self, photon: &Photon){
-        let crossing = self.calculate_photon_crossing(photon);
-        self.insert_new_crossing(crossing)
+    fn calculate_photon_crossing(&self, receiver: &ReceiverData<V>) -> Option<PhotonCrossing<V>>{
+        let photon_emmit_tau = self.segment.tau_at_past_light_cone(&receiver.m_pos)?;
+        let (photon_emmit_pos, photon_emmit_velocity) = self.segment.state_at_tau(photon_emmit_tau);
+        let emmit_minus_curr = photon_emmit_pos - receiver.m_pos;
+        let photon_emmit_pos_in_receiver_frame = emmit_minus_curr.lorentz_transform(receiver.velocity);
+        // Doppler shift: ratio of the photon's energy measured by the receiver and by the source.
+        let direction = (receiver.m_pos.pos - photon_emmit_pos.pos).normalise();
+        let receiver_factor = velocity::gamma(receiver.velocity) * (1.0 - V::dot(direction, receiver.velocity));
+        let source_factor = velocity::gamma(photon_emmit_velocity) * (1.0 - V::dot(direction, photon_emmit_velocity));
+        Some(PhotonCrossing{
+            photon_emmit_pos,
+            photon_emmit_tau,
+            photon_emmit_velocity,
+            photon_emmit_pos_in_receiver_frame,
+            relative_freq: receiver_factor / source_factor,
+        })
     }
+}
 
-    fn calculate_new_photons_for_constant_velocity(&mut self){
-        if let Some(vec) = self.constant_velocity_dx{
-            let mut new_photon_pos = self.last_photons.back().expect("checked").photon_emmit_pos + vec;
-            while (self.receiver_current_pos - new_photon_pos).is_time_or_light_like() && self.receiver_current_pos.time > new_photon_pos.time {
-                self.insert_new_crossing(self.calculate_photon_crossing_based_on_pos(new_photon_pos));
-                new_photon_pos = new_photon_pos + vec;
-            }
-        }
+impl<V: SpatialVector> PhotonCrossing<V> {
+    /// Power per unit area received from a source of `luminosity`. The light spreads over a
+    /// sphere in the source's rest frame, and the Doppler factor enters four times: once for
+    /// the photon energy, once for their arrival rate and twice for the aberration that
+    /// concentrates the light forward. Distances below `min_distance` are clamped to it.
+    fn apparent_intensity(&self, luminosity: f64, min_distance: f64) -> f64 {
+        let distance = self.photon_emmit_pos_in_receiver_frame.pos.length().max(min_distance);
+        luminosity * self.relative_freq.powi(4) / (4.0 * std::f64::consts::PI * distance * distance)
     }
-    fn insert_new_crossing(&mut self, crossing: PhotonCrossing){
-        self.last_photons.push_back(crossing);
-        if self.last_photons.len() > LAST_PHOTONS_COUNT{
-            self.last_photons.pop_front();
-        }
-        self.calculate_obj_properties()
+}
+
+/// Sizes of the queues of samples still travelling to a receiver.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PhotonQueueStats{
+    queued_photons: usize,
+    longest_queue: usize,
+    coalesced_photons: usize,
+    dropped_photons: usize,
+}
+
+impl PhotonQueueStats{
+    /// Samples waiting in all queues.
+    pub fn get_queued_photons(&self) -> usize {
+        self.queued_photons
     }
 
-    fn calculate_photon_crossing(&self, photon: &Photon) -> PhotonCrossing{
-        self.calculate_photon_crossing_based_on_pos(photon.get_emmit_pos())
+    /// Samples waiting for the busiest emitting point.
+    pub fn get_longest_queue(&self) -> usize {
+        self.longest_queue
     }
-    fn calculate_photon_crossing_based_on_pos(&self, photon_emmit_pos: MVector<f64>) -> PhotonCrossing{
-        let emmit_minus_curr = photon_emmit_pos - self.receiver_current_pos;
-        let photon_emmit_pos_in_receiver_frame = emmit_minus_curr.lorentz_transform(self.receiver_v);
-        let time_from_catch = photon_emmit_pos_in_receiver_frame.time.abs() - photon_emmit_pos_in_receiver_frame.pos.length();
-        PhotonCrossing{
-            photon_emmit_pos,
-            photon_emmit_pos_in_receiver_frame,
-            time_from_catch,
+
+    /// Samples removed because the previous one already described the same motion.
+    pub fn get_coalesced_photons(&self) -> usize {
+        self.coalesced_photons
+    }
+
+    /// Samples removed to stay within the budget although they changed the motion.
+    pub fn get_dropped_photons(&self) -> usize {
+        self.dropped_photons
+    }
+
+    /// Statistics of both sets of queues together.
+    pub fn merge(&self, other: &Self) -> Self {
+        Self{
+            queued_photons: self.queued_photons + other.queued_photons,
+            longest_queue: self.longest_queue.max(other.longest_queue),
+            coalesced_photons: self.coalesced_photons + other.coalesced_photons,
+            dropped_photons: self.dropped_photons + other.dropped_photons,
         }
     }
 }
 
+type TrackerProperties<V> = (V, Vec<V>, Option<Vec<V>>, f64, f64, MVector<f64, V>, f64);
+
+pub struct ObjectTracker<V = Vector2D<f64>>{
 
-pub struct ObjectTracker{
+    last_visible_source: HashMap<PhotonEmittingPosition, TrackedSource<V>>,
+    waiting_photons_queue: HashMap<PhotonEmittingPosition, VecDeque<Photon<V>>>,
+    queue_budget: usize,
+    coalesced_photons: usize,
+    dropped_photons: usize,
 
-    last_visible_source: HashMap<PhotonEmittingPosition, TrackedSource>,
-    waiting_photons_queue: HashMap<PhotonEmittingPosition, VecDeque<Photon>>,
+    outline_size: usize,
 
-    relative_visible_position: Vector2D<f64>,
-    basis_x: Vector2D<f64>,
-    basis_y: Vector2D<f64>,
+    relative_visible_position: V,
+    basis: Vec<V>,
+    visible_outline: Vec<V>,
     relative_frequency: f64,
-    visible_m_vector: MVector<f64>,
+    observed_period: f64,
+    observed_color: Color,
+    /// Frequency ratio and spectrum revision `observed_color` was computed for.
+    observed_color_key: Option<(f64, u64)>,
+    apparent_intensity: f64,
+    visible_m_vector: MVector<f64, V>,
+    visible_tau: f64,
 
     object_was_seen: bool,
+    creation_event: Option<MVector<f64, V>>,
+    creation_light_arrived: bool,
+    destruction_event: Option<MVector<f64, V>>,
+    object_was_destroyed: bool,
 
 }
 
-impl ObjectTracker {
-    pub fn get_relative_visible_position(&self) -> &Vector2D<f64> {
+impl<V: SpatialVector> ObjectTracker<V> {
+    pub fn get_relative_visible_position(&self) -> &V {
         &self.relative_visible_position
     }
 
-    pub fn get_basis_x(&self) -> &Vector2D<f64> {
-        &self.basis_x
+    pub fn get_basis(&self) -> &[V] {
+        &self.basis
+    }
+
+    pub fn get_basis_x(&self) -> &V {
+        &self.basis[0]
+    }
+
+    pub fn get_basis_y(&self) -> &V {
+        &self.basis[1]
     }
 
-    pub fn get_basis_y(&self) -> &Vector2D<f64> {
-        &self.basis_y
+    /// Seen positions of the source's outline points relative to the receiver, in outline
+    /// order. Every point is traced on its own, so distortions that no linear basis can
+    /// describe, such as Terrell rotation of nearby objects, show up here. Empty until every
+    /// point has been seen; when the outline changes, the previous one is kept until all
+    /// new points have been seen.
+    pub fn get_visible_outline(&self) -> &[V] {
+        &self.visible_outline
     }
 
     pub fn get_relative_frequency(&self) -> f64 {
         self.relative_frequency
     }
 
-    pub fn get_visible_m_vector(&self) -> &MVector<f64> {
+    /// Time between the arrivals of two ticks of the source's signal, e.g. the blinking period
+    /// of a beacon, measured by the receiver. It follows from the relative frequency at the
+    /// visible event, as samples are only sent when the motion changes; `get_visible_tau`
+    /// tells which tick is seen. Zero until the object was seen.
+    pub fn get_observed_period(&self) -> f64 {
+        self.observed_period
+    }
+
+    /// Doppler-shifted color of the source's spectrum, black until the object was seen.
+    pub fn get_observed_color(&self) -> &Color {
+        &self.observed_color
+    }
+
+    /// Power per unit area received from the source, including Doppler shift, relativistic
+    /// beaming and the inverse-square falloff along the light path. Zero until the object
+    /// was seen.
+    pub fn get_apparent_intensity(&self) -> f64 {
+        self.apparent_intensity
+    }
+
+    pub fn get_visible_m_vector(&self) -> &MVector<f64, V> {
         &self.visible_m_vector
     }
 
+    /// Proper time of the source at the visible event.
+    pub fn get_visible_tau(&self) -> f64 {
+        self.visible_tau
+    }
+
     pub fn get_object_was_seen(&self) -> bool {
         self.object_was_seen
     }
+
+    /// Motion of the center as last seen, valid from the visible event on until new light arrives.
+    pub fn get_visible_segment(&self) -> Option<&MotionSegment<V>> {
+        self.last_visible_source.get(&PhotonEmittingPosition::CENTER).map(|source| &source.segment)
+    }
+
+    /// Objects without a creation event, like a previous frame object, count as created.
+    pub fn get_visibility(&self) -> Visibility {
+        if self.object_was_destroyed {
+            Visibility::Destroyed
+        } else if self.creation_event.is_none() || self.creation_light_arrived {
+            Visibility::Visible
+        } else {
+            Visibility::NotYetCreated
+        }
+    }
+
+    /// Event at which the object was registered, if it was created by its frame.
+    pub fn get_creation_event(&self) -> Option<&MVector<f64, V>> {
+        self.creation_event.as_ref()
+    }
+
+    /// Event at which the object was unregistered, if it was.
+    pub fn get_destruction_event(&self) -> Option<&MVector<f64, V>> {
+        self.destruction_event.as_ref()
+    }
+
+    /// Whether light from the destruction event has reached the receiver. The last visible
+    /// state is kept until then.
+    pub fn get_object_was_destroyed(&self) -> bool {
+        self.object_was_destroyed
+    }
+
+    /// Maximum number of samples waiting per emitting point.
+    pub fn get_queue_budget(&self) -> usize {
+        self.queue_budget
+    }
+
+    pub fn get_queue_stats(&self) -> PhotonQueueStats {
+        PhotonQueueStats{
+            queued_photons: self.waiting_photons_queue.values().map(VecDeque::len).sum(),
+            longest_queue: self.waiting_photons_queue.values().map(VecDeque::len).max().unwrap_or(0),
+            coalesced_photons: self.coalesced_photons,
+            dropped_photons: self.dropped_photons,
+        }
+    }
+}
+
+impl ObjectTracker<Vector3D<f64>> {
+    pub fn get_basis_z(&self) -> &Vector3D<f64> {
+        &self.basis[2]
+    }
 }
 
-impl ObjectTracker{
+impl<V: SpatialVector> ObjectTracker<V>{
 
     pub(crate) fn new () -> Self{
         Self{
             last_visible_source: Default::default(),
             waiting_photons_queue: Default::default(),
+            queue_budget: DEFAULT_PHOTON_QUEUE_BUDGET,
+            coalesced_photons: 0,
+            dropped_photons: 0,
+            outline_size: 0,
             relative_visible_position: Default::default(),
-            basis_x: Vector2D::new(1.0, 0.0),
-            basis_y: Vector2D::new(0.0, 1.0),
+            basis: Self::default_basis(),
+            visible_outline: vec![],
             relative_frequency: 1.0,
+            observed_period: 0.0,
+            observed_color: Color::default(),
+            observed_color_key: None,
+            apparent_intensity: 0.0,
             visible_m_vector: Default::default(),
+            visible_tau: 0.0,
             object_was_seen: false,
+            creation_event: None,
+            creation_light_arrived: false,
+            destruction_event: None,
+            object_was_destroyed: false,
         }
     }
-    pub(crate) fn recalculate_properties(&mut self, source: &MObject, receiver: &ReceiverData, delta_tau: f64) {
+    pub(crate) fn recalculate_properties(&mut self, source: &MObject<V>, receiver: &ReceiverData<V>) {
+        if let Some(event) = self.creation_event && receiver.has_seen(&event) {
+            self.creation_light_arrived = true;
+        }
+        if let Some(event) = self.destruction_event && receiver.has_seen(&event) {
+            self.object_was_destroyed = true;
+        }
+        if self.object_was_destroyed {
+            return
+        }
+        self.process_new_photons(receiver);
         self.last_visible_source.values_mut()
-            .for_each(|v|{
-                v.receiver_v = receiver.velocity;
-                v.receiver_current_pos = receiver.m_pos;
-                v.last_photons.iter_mut().for_each(|s|
-                    {
-                        s.time_from_catch += delta_tau;
-                    })
-            });
-        self.process_new_photons(source, receiver);
-        if let Some(properties) = self.calculate_properties(){
-            (self.relative_visible_position, self.basis_x, self.basis_y, self.relative_frequency, self.visible_m_vector) = properties;
+            .for_each(|tracked_source| tracked_source.recalculate(receiver));
+        if let Some(properties) = self.calculate_properties(source){
+            let outline;
+            (self.relative_visible_position, self.basis, outline, self.relative_frequency, self.apparent_intensity, self.visible_m_vector, self.visible_tau) = properties;
+            if let Some(outline) = outline {
+                self.visible_outline = outline;
+            }
+            self.observed_period = source.get_update_ratio() / self.relative_frequency;
+            let color_key = Some((self.relative_frequency, source.get_spectrum_revision()));
+            if self.observed_color_key != color_key {
+                self.observed_color = source.get_spectrum().observed_color(self.relative_frequency);
+                self.observed_color_key = color_key;
+            }
             self.object_was_seen = true
         }
     }
 
-    pub(crate) fn track_photons(&mut self, emitted_photons: Vec<Photon>){
+    /// Tracker that sees `object` from the oldest sample it still keeps, so that light still in
+    /// flight to a new receiver is not lost.
+    pub(crate) fn from_history(object: &MObject<V>, queue_budget: usize) -> Self{
+        let mut res = Self::new();
+        res.set_queue_budget(queue_budget);
+        res.track_photons(object.get_emitted_samples().cloned().collect());
+        res
+    }
+
+    /// Tracker of the same object for another receiver, knowing the same creation and destruction.
+    pub(crate) fn for_receiver(&self, object: &MObject<V>) -> Self{
+        let mut res = Self::from_history(object, self.queue_budget);
+        res.creation_event = self.creation_event;
+        res.destruction_event = self.destruction_event;
+        res
+    }
+
+    pub(crate) fn create_at(&mut self, event: MVector<f64, V>){
+        self.creation_event = Some(event);
+    }
+
+    pub(crate) fn destroy_at(&mut self, event: MVector<f64, V>){
+        self.destruction_event = Some(event);
+    }
+
+    pub(crate) fn track_photons(&mut self, emitted_photons: Vec<Photon<V>>){
         emitted_photons.into_iter()
             .for_each(|emitted_photon|{
                 let photon_emmit_type = emitted_photon.get_emmit_type();
                 self.waiting_photons_queue
                     .entry(photon_emmit_type)
-                    .or_insert_with(VecDeque::new)
+                    .or_default()
                     .push_back(emitted_photon);
-            })
+            });
+        self.limit_queues();
     }
-    fn process_new_photons(&mut self, source: &MObject, receiver: &ReceiverData){
-        self.process_photons_of_type(source, receiver, PhotonEmittingPosition::CENTER);
-        self.process_photons_of_type(source, receiver, PhotonEmittingPosition::BOTTOM);
-        self.process_photons_of_type(source, receiver, PhotonEmittingPosition::TOP);
-        self.process_photons_of_type(source, receiver, PhotonEmittingPosition::FRONT);
-        self.process_photons_of_type(source, receiver, PhotonEmittingPosition::BACK);
+
+    /// Budgets below two are raised to two, so that both the next and the newest sample are kept.
+    pub(crate) fn set_queue_budget(&mut self, queue_budget: usize){
+        self.queue_budget = queue_budget.max(2);
+        self.limit_queues();
     }
 
-    fn process_photons_of_type(&mut self, source: &MObject, receiver: &ReceiverData, photon_emitting_position: PhotonEmittingPosition){
+    /// Shrinks queues over the budget. Samples that only restate the motion of the previous
+    /// one go first, as they do not change what the receiver sees. Then the samples whose
+    /// removal moves the seen worldline the least are dropped.
+    fn limit_queues(&mut self){
+        for queue in self.waiting_photons_queue.values_mut() {
+            if queue.len() <= self.queue_budget {
+                continue
+            }
+            let before = queue.len();
+            queue.retain({
+                let mut previous: Option<Photon<V>> = None;
+                move |photon| {
+                    let redundant = previous.as_ref().is_some_and(|previous| restates(previous, photon));
+                    if !redundant {
+                        previous = Some(photon.clone());
+                    }
+                    !redundant
+                }
+            });
+            self.coalesced_photons += before - queue.len();
+            if queue.len() > self.queue_budget {
+                // Thin a quarter below the budget so that this does not run on every sample.
+                self.dropped_photons += thin_queue(queue, (self.queue_budget - self.queue_budget / 4).max(2), |photon| photon);
+            }
+        }
+    }
+    fn default_basis() -> Vec<V> {
+        (0..V::DIM).map(V::axis).collect()
+    }
+
+    fn process_new_photons(&mut self, receiver: &ReceiverData<V>){
+        let positions: Vec<PhotonEmittingPosition> = self.waiting_photons_queue.keys().copied().collect();
+        for photon_emitting_position in positions {
+            self.process_photons_of_type(receiver, photon_emitting_position);
+        }
+    }
+
+    fn process_photons_of_type(&mut self, receiver: &ReceiverData<V>, photon_emitting_position: PhotonEmittingPosition){
         while let Some(photon) = self.fetch_next_photon(receiver, photon_emitting_position) {
+            if photon_emitting_position == PhotonEmittingPosition::CENTER && photon.get_outline_size() != self.outline_size {
+                // Points dropped from the outline stop being seen once light of the change arrives.
+                let outline_size = photon.get_outline_size();
+                self.last_visible_source.retain(|position, _| match position {
+                    PhotonEmittingPosition::CENTER => true,
+                    PhotonEmittingPosition::OUTLINE(i) => *i < outline_size,
+                });
+                self.outline_size = outline_size;
+            }
             self.last_visible_source.entry(photon_emitting_position)
                 .and_modify(|last|last.insert_into(&photon)).or_insert(
-                TrackedSource::new(&photon, source, receiver)
+                TrackedSource::new(&photon)
             );
         }
     }
 
-    fn fetch_next_photon(&mut self, receiver: &ReceiverData, photon_emitting_position: PhotonEmittingPosition) -> Option<Photon>{
-        if let Some(tracked_source) = self.last_visible_source.get_mut(&photon_emitting_position) && tracked_source.constant_velocity_dx.is_some(){
-            tracked_source.calculate_new_photons_for_constant_velocity();
-            None
-        }else{
-            let queue = self.waiting_photons_queue.get_mut(&photon_emitting_position)?;
-            let is_first_photon_visible = {
-                let first_photon = queue.front()?;
-                (receiver.m_pos - first_photon.get_emmit_pos()).is_time_or_light_like()
-            };
-            if is_first_photon_visible {
-                return queue.pop_front()
-            }
-            None
+    fn fetch_next_photon(&mut self, receiver: &ReceiverData<V>, photon_emitting_position: PhotonEmittingPosition) -> Option<Photon<V>>{
+        let queue = self.waiting_photons_queue.get_mut(&photon_emitting_position)?;
+        let is_first_photon_visible = {
+            let first_photon = queue.front()?;
+            let emmit_pos = first_photon.get_emmit_pos();
+            receiver.has_seen(&emmit_pos)
+        };
+        if is_first_photon_visible {
+            return queue.pop_front()
         }
+        None
     }
 
-    fn calculate_properties(&self) -> Option<(Vector2D<f64>, Vector2D<f64>, Vector2D<f64>, f64, MVector<f64>)>{
-        let last_visible_center = self.last_visible_source.get(&PhotonEmittingPosition::CENTER)?;
-        let current_m_vector = last_visible_center.current_m_vector()?;
-        let relative_pos = last_visible_center.relative_position()?;
-        let (basis_x, basis_y) = self.calculate_transform(&relative_pos).unwrap_or((Vector2D::new(1.0, 0.0), Vector2D::new(0.0, 1.0)));
-        Some((relative_pos, basis_x, basis_y, last_visible_center.relative_frequency()?, current_m_vector.into()))
-    }
-
-    fn calculate_transform(&self, center: &Vector2D<f64>) -> Option<(Vector2D<f64>, Vector2D<f64>)> {
-        if let (Some(back), Some(front), Some(top), Some(bottom)) = (
-            self.last_visible_source.get(&PhotonEmittingPosition::BACK),
-            self.last_visible_source.get(&PhotonEmittingPosition::FRONT),
-            self.last_visible_source.get(&PhotonEmittingPosition::TOP),
-            self.last_visible_source.get(&PhotonEmittingPosition::BOTTOM),
-        ) {
-            let radius = back.object_radius;
-            let a = [
-                Vector2D::new(radius, 0.0),
-                Vector2D::new(-radius, 0.0),
-                Vector2D::new(0.0, radius),
-                Vector2D::new(0.0, -radius),
-            ];
-            let b = [
-                front.relative_position()? - *center,
-                back.relative_position()? - *center,
-                top.relative_position()? - *center,
-                bottom.relative_position()? - *center,
-            ];
-            let mut sum_aa = [[0.0; 2]; 2];
-            let mut sum_ab = [[0.0; 2]; 2];
-            for i in 0..4 {
-                let ax = a[i].x;
-                let ay = a[i].y;
-                let bx = b[i].x;
-                let by = b[i].y;
-
-                sum_aa[0][0] += ax * ax;
-                sum_aa[0][1] += ax * ay;
-                sum_aa[1][0] += ay * ax;
-                sum_aa[1][1] += ay * ay;
-
-                sum_ab[0][0] += bx * ax;
-                sum_ab[0][1] += bx * ay;
-                sum_ab[1][0] += by * ax;
-                sum_ab[1][1] += by * ay;
+    fn calculate_properties(&self, source: &MObject<V>) -> Option<TrackerProperties<V>>{
+        let crossing = self.last_visible_source.get(&PhotonEmittingPosition::CENTER)?.crossing.as_ref()?;
+        let relative_pos = crossing.photon_emmit_pos_in_receiver_frame.pos;
+        let outline = self.calculate_outline();
+        let basis = outline.as_ref()
+            .and_then(|outline| self.calculate_transform(&relative_pos, outline))
+            .unwrap_or_else(Self::default_basis);
+        let intensity = crossing.apparent_intensity(source.get_luminosity(), source.get_radius());
+        Some((relative_pos, basis, outline, crossing.relative_freq, intensity, crossing.photon_emmit_pos, crossing.photon_emmit_tau))
+    }
+
+    fn calculate_outline(&self) -> Option<Vec<V>> {
+        (0..self.outline_size)
+            .map(|i| self.last_visible_source.get(&PhotonEmittingPosition::OUTLINE(i))?.relative_position())
+            .collect()
+    }
+
+    /// Linear map from the rest frame outline to the seen one that fits best in the least
+    /// squares sense.
+    fn calculate_transform(&self, center: &V, outline: &[V]) -> Option<Vec<V>> {
+        let pairs = outline.iter().enumerate()
+            .filter_map(|(i, seen)| Some((self.last_visible_source.get(&PhotonEmittingPosition::OUTLINE(i))?.rest_frame_offset, *seen - *center)));
+        let dim = V::DIM;
+        let mut sum_aa = [[0.0; 3]; 3];
+        let mut sum_ab = [[0.0; 3]; 3];
+        for (a, b) in pairs {
+            for r in 0..dim {
+                for c in 0..dim {
+                    sum_aa[r][c] += a.component(r) * a.component(c);
+                    sum_ab[r][c] += b.component(r) * a.component(c);
+                }
             }
+        }
+        let inv_aa = invert_matrix(sum_aa, dim)?;
+        let basis = (0..dim)
+            .map(|c| {
+                let column: Vec<f64> = (0..dim)
+                    .map(|r| (0..dim).map(|k| sum_ab[r][k] * inv_aa[k][c]).sum())
+                    .collect();
+                V::from_components(&column)
+            })
+            .collect();
+        Some(basis)
+    }
+}
 
-            let det = sum_aa[0][0] * sum_aa[1][1] - sum_aa[0][1] * sum_aa[1][0];
-            if det.abs() < 1e-8 {
-                return None;
+fn invert_matrix(mut m: [[f64; 3]; 3], dim: usize) -> Option<[[f64; 3]; 3]> {
+    let mut inv = [[0.0; 3]; 3];
+    for (i, row) in inv.iter_mut().enumerate().take(dim) {
+        row[i] = 1.0;
+    }
+    for col in 0..dim {
+        let pivot = (col..dim).max_by(|a, b| m[*a][col].abs().total_cmp(&m[*b][col].abs()))?;
+        if m[pivot][col].abs() < 1e-8 {
+            return None;
+        }
+        m.swap(col, pivot);
+        inv.swap(col, pivot);
+        let p = m[col][col];
+        for k in 0..dim {
+            m[col][k] /= p;
+            inv[col][k] /= p;
+        }
+        for row in 0..dim {
+            if row != col {
+                let factor = m[row][col];
+                for k in 0..dim {
+                    m[row][k] -= factor * m[col][k];
+                    inv[row][k] -= factor * inv[col][k];
+                }
             }
+        }
+    }
+    Some(inv)
+}
 
-            let inv_aa = [
-                [ sum_aa[1][1] / det, -sum_aa[0][1] / det ],
-                [ -sum_aa[1][0] / det, sum_aa[0][0] / det ],
-            ];
+/// How far `earlier`, continued to the start time of `later`, is from it. Outline points age
+/// at their own rate, so their segments are compared at the same coordinate time.
+fn deviation<V: SpatialVector>(earlier: &MotionSegment<V>, later: &MotionSegment<V>) -> f64 {
+    let continued = earlier.split_at(earlier.tau_at_time(later.get_start().time));
+    let position = *continued.get_start() - *later.get_start();
+    position.time.abs() + position.pos.length()
+        + (*continued.get_start_velocity() - *later.get_start_velocity()).length()
+        + (*continued.get_acceleration() - *later.get_acceleration()).length()
+}
 
-            let m00 = sum_ab[0][0] * inv_aa[0][0] + sum_ab[0][1] * inv_aa[1][0];
-            let m01 = sum_ab[0][0] * inv_aa[0][1] + sum_ab[0][1] * inv_aa[1][1];
-            let m10 = sum_ab[1][0] * inv_aa[0][0] + sum_ab[1][1] * inv_aa[1][0];
-            let m11 = sum_ab[1][0] * inv_aa[0][1] + sum_ab[1][1] * inv_aa[1][1];
+fn restates<V: SpatialVector>(previous: &Photon<V>, photon: &Photon<V>) -> bool {
+    previous.get_outline_size() == photon.get_outline_size()
+        && (*previous.get_rest_frame_offset() - *photon.get_rest_frame_offset()).length() <= COALESCE_TOLERANCE
+        && deviation(previous.get_segment(), photon.get_segment()) <= COALESCE_TOLERANCE * (1.0 + photon.get_emmit_pos().time.abs())
+}
 
-            let basis_x = Vector2D::new(m00, m10);
-            let basis_y = Vector2D::new(m01, m11);
-            return Some((basis_x, basis_y));
-        }
-        None
+/// Error made on the next sample's start by dropping the `i`-th one of `queue`, whose entries
+/// are judged by the sample `photon` returns for them. The first and the last sample are
+/// never dropped.
+fn drop_error<V: SpatialVector, T>(queue: &VecDeque<T>, i: usize, photon: impl Fn(&T) -> &Photon<V>) -> f64 {
+    if i == 0 || i + 1 >= queue.len() || photon(&queue[i]).get_outline_size() != photon(&queue[i - 1]).get_outline_size() {
+        return f64::INFINITY
     }
+    deviation(photon(&queue[i - 1]).get_segment(), photon(&queue[i + 1]).get_segment())
 }
 
+/// Drops the samples with the smallest error until `queue` holds `target` of them. Returns
+/// the number of dropped samples.
+pub(crate) fn thin_queue<V: SpatialVector, T>(queue: &mut VecDeque<T>, target: usize, photon: impl Fn(&T) -> &Photon<V> + Copy) -> usize {
+    let mut errors: Vec<f64> = (0..queue.len()).map(|i| drop_error(queue, i, photon)).collect();
+    let mut dropped = 0;
+    while queue.len() > target {
+        let Some((i, _)) = errors.iter().enumerate()
+            .filter(|(_, error)| error.is_finite())
+            .min_by(|a, b| a.1.total_cmp(b.1)) else {
+            break
+        };
+        queue.remove(i);
+        errors.remove(i);
+        errors[i - 1] = drop_error(queue, i - 1, photon);
+        errors[i] = drop_error(queue, i, photon);
+        dropped += 1;
+    }
+    dropped
+}
@@ -1,35 +1,61 @@
+use vector2d::Vector2D;
 use crate::m_vector::MVector;
+use crate::motion::MotionSegment;
+use crate::spatial_vector::SpatialVector;
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum PhotonEmittingPosition{
     CENTER,
-    BACK,
-    FRONT,
-    TOP,
-    BOTTOM
+    /// Point of the emitting object's outline, by index.
+    OUTLINE(usize),
 }
 
+/// Sample of an emitting position's worldline. The motion segment starting at the emission
+/// event describes the source until the next sample, so light can be traced exactly in between.
 #[derive(Clone)]
-pub struct Photon{
-    m_pos: MVector<f64>,
-    photon_pos: PhotonEmittingPosition
+pub struct Photon<V = Vector2D<f64>>{
+    segment: MotionSegment<V>,
+    photon_pos: PhotonEmittingPosition,
+    rest_frame_offset: V,
+    outline_size: usize,
 }
 
-impl Photon{
-    pub fn new(m_pos: MVector<f64>, photon_pos: PhotonEmittingPosition) -> Self {
+impl<V: SpatialVector> Photon<V>{
+    pub fn new(segment: MotionSegment<V>, photon_pos: PhotonEmittingPosition, rest_frame_offset: V, outline_size: usize) -> Self {
         Self{
-            m_pos,
+            segment,
             photon_pos,
+            rest_frame_offset,
+            outline_size,
         }
     }
 }
 
-impl Photon{
+impl<V: SpatialVector> Photon<V>{
     pub fn get_emmit_type(&self) -> PhotonEmittingPosition {
         self.photon_pos
     }
 
-    pub fn get_emmit_pos(&self) -> MVector<f64> {
-        self.m_pos
+    pub fn get_emmit_pos(&self) -> MVector<f64, V> {
+        *self.segment.get_start()
     }
-}
\ No newline at end of file
+
+    pub fn get_emmit_tau(&self) -> f64 {
+        self.segment.get_start_tau()
+    }
+
+    pub fn get_segment(&self) -> &MotionSegment<V> {
+        &self.segment
+    }
+
+    /// Position of the emitting point relative to the center, in the source's rest frame.
+    pub fn get_rest_frame_offset(&self) -> &V {
+        &self.rest_frame_offset
+    }
+
+    /// Number of outline points the source had at emission.
+    pub fn get_outline_size(&self) -> usize {
+        self.outline_size
+    }
+}
@@ -0,0 +1,94 @@
+use std::fmt::Debug;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+use vector2d::Vector2D;
+use crate::vector3d::Vector3D;
+
+/// Spatial part of an `MVector`, implemented for 2D (`Vector2D<f64>`) and 3D (`Vector3D<f64>`) space.
+pub trait SpatialVector:
+    Copy + Default + Debug + Send + Sync + 'static
+    + Add<Output = Self> + Sub<Output = Self> + Neg<Output = Self>
+    + Mul<f64, Output = Self> + Div<f64, Output = Self>
+{
+    const DIM: usize;
+
+    fn axis(i: usize) -> Self;
+
+    fn component(&self, i: usize) -> f64;
+
+    fn dot(v1: Self, v2: Self) -> f64;
+
+    fn zero() -> Self {
+        Self::default()
+    }
+
+    fn length_squared(&self) -> f64 {
+        Self::dot(*self, *self)
+    }
+
+    fn length(&self) -> f64 {
+        self.length_squared().sqrt()
+    }
+
+    fn normalise(&self) -> Self {
+        let len = self.length();
+        if len == 0.0 {
+            *self
+        } else {
+            *self / len
+        }
+    }
+
+    fn from_components(components: &[f64]) -> Self {
+        (0..Self::DIM).fold(Self::zero(), |acc, i| acc + Self::axis(i) * components.get(i).copied().unwrap_or(0.0))
+    }
+}
+
+impl SpatialVector for Vector2D<f64>{
+    const DIM: usize = 2;
+
+    fn axis(i: usize) -> Self {
+        match i {
+            0 => Vector2D::new(1.0, 0.0),
+            1 => Vector2D::new(0.0, 1.0),
+            _ => Vector2D::new(0.0, 0.0),
+        }
+    }
+
+    fn component(&self, i: usize) -> f64 {
+        match i {
+            0 => self.x,
+            1 => self.y,
+            _ => 0.0,
+        }
+    }
+
+    fn dot(v1: Self, v2: Self) -> f64 {
+        Vector2D::dot(v1, v2)
+    }
+}
+
+impl SpatialVector for Vector3D<f64>{
+    const DIM: usize = 3;
+
+    fn axis(i: usize) -> Self {
+        match i {
+            0 => Vector3D::new(1.0, 0.0, 0.0),
+            1 => Vector3D::new(0.0, 1.0, 0.0),
+            2 => Vector3D::new(0.0, 0.0, 1.0),
+            _ => Vector3D::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    fn component(&self, i: usize) -> f64 {
+        match i {
+            0 => self.x,
+            1 => self.y,
+            2 => self.z,
+            _ => 0.0,
+        }
+    }
+
+    fn dot(v1: Self, v2: Self) -> f64 {
+        Vector3D::dot(v1, v2)
+    }
+}
@@ -0,0 +1,186 @@
+/// Second radiation constant hc/k in nanometre kelvins.
+const PLANCK_C2: f64 = 1.438_776_9e7;
+
+const VISIBLE_FROM: f64 = 380.0;
+const VISIBLE_TO: f64 = 780.0;
+const WAVELENGTH_STEP: f64 = 5.0;
+
+/// Temperature of a blackbody close to the sRGB white point.
+pub const WHITE_TEMPERATURE: f64 = 6504.0;
+
+#[derive(Clone, Debug)]
+enum Distribution{
+    Blackbody(f64),
+    /// `(wavelength, radiance)` pairs sorted by wavelength, linearly interpolated.
+    Sampled(Vec<(f64, f64)>),
+}
+
+/// Emission spectrum of an object in its rest frame. Wavelengths are in nanometres and
+/// radiance is relative, so only the shape of the spectrum matters.
+#[derive(Clone, Debug)]
+pub struct Spectrum{
+    distribution: Distribution,
+    luminance: f64,
+}
+
+impl Default for Spectrum{
+    fn default() -> Self {
+        Self::blackbody(WHITE_TEMPERATURE)
+    }
+}
+
+impl Spectrum{
+    /// Thermal spectrum at `temperature` kelvins.
+    pub fn blackbody(temperature: f64) -> Self{
+        Self::from_distribution(Distribution::Blackbody(temperature.max(0.0)))
+    }
+
+    /// Spectrum given by `(wavelength, radiance)` samples. It is zero outside the sampled range.
+    pub fn sampled(mut samples: Vec<(f64, f64)>) -> Self{
+        samples.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self::from_distribution(Distribution::Sampled(samples))
+    }
+
+    /// Relative spectral radiance emitted at `wavelength`.
+    pub fn radiance(&self, wavelength: f64) -> f64{
+        match &self.distribution {
+            Distribution::Blackbody(temperature) => {
+                if *temperature <= 0.0 || wavelength <= 0.0 {
+                    return 0.0
+                }
+                1.0 / (wavelength.powi(5) * (PLANCK_C2 / (wavelength * temperature)).exp_m1())
+            }
+            Distribution::Sampled(samples) => {
+                let i = samples.partition_point(|(w, _)| *w < wavelength);
+                match (i.checked_sub(1).map(|j| samples[j]), samples.get(i)) {
+                    (Some((w0, r0)), Some((w1, r1))) => r0 + (r1 - r0) * (wavelength - w0) / (w1 - w0),
+                    (None, Some((w1, r1))) if *w1 == wavelength => *r1,
+                    _ => 0.0,
+                }
+            }
+        }
+    }
+
+    /// Color of the spectrum received with `relative_frequency`, as returned by
+    /// `ObjectTracker::get_relative_frequency`. Every wavelength is divided by the ratio, so
+    /// light moves in and out of the visible range. A blackbody stays a blackbody at the
+    /// scaled temperature. The unshifted spectrum has a luminance of one.
+    pub fn observed_color(&self, relative_frequency: f64) -> Color{
+        if self.luminance <= 0.0 || relative_frequency <= 0.0 || !relative_frequency.is_finite() {
+            return Color::default()
+        }
+        let [x, y, z] = self.integrate_xyz(relative_frequency);
+        Color::from_xyz([x / self.luminance, y / self.luminance, z / self.luminance])
+    }
+}
+
+impl Spectrum{
+    fn from_distribution(distribution: Distribution) -> Self{
+        let mut res = Self{
+            distribution,
+            luminance: 0.0,
+        };
+        res.luminance = res.integrate_xyz(1.0)[1];
+        res
+    }
+
+    fn integrate_xyz(&self, relative_frequency: f64) -> [f64; 3]{
+        let mut res = [0.0; 3];
+        let mut wavelength = VISIBLE_FROM;
+        while wavelength <= VISIBLE_TO {
+            let radiance = self.radiance(wavelength * relative_frequency);
+            let matching = color_matching(wavelength);
+            for (sum, m) in res.iter_mut().zip(matching) {
+                *sum += radiance * m;
+            }
+            wavelength += WAVELENGTH_STEP;
+        }
+        res
+    }
+}
+
+/// Observed color in CIE XYZ, with conversions to sRGB.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Color{
+    xyz: [f64; 3],
+}
+
+impl Color{
+    pub fn from_xyz(xyz: [f64; 3]) -> Self{
+        Self{
+            xyz,
+        }
+    }
+
+    pub fn get_xyz(&self) -> &[f64; 3] {
+        &self.xyz
+    }
+
+    pub fn luminance(&self) -> f64{
+        self.xyz[1]
+    }
+
+    /// Linear sRGB components. They can be negative or above one for colors out of gamut.
+    pub fn linear_rgb(&self) -> [f64; 3]{
+        let [x, y, z] = self.xyz;
+        [
+            3.2406 * x - 1.5372 * y - 0.4986 * z,
+            -0.9689 * x + 1.8758 * y + 0.0415 * z,
+            0.0557 * x - 0.2040 * y + 1.0570 * z,
+        ]
+    }
+
+    /// Gamma-encoded sRGB components, clamped to `[0, 1]`.
+    pub fn srgb(&self) -> [f64; 3]{
+        self.linear_rgb().map(|c| {
+            let c = c.clamp(0.0, 1.0);
+            if c <= 0.003_130_8 {
+                12.92 * c
+            } else {
+                1.055 * c.powf(1.0 / 2.4) - 0.055
+            }
+        })
+    }
+
+    pub fn srgb8(&self) -> [u8; 3]{
+        self.srgb().map(|c| (c * 255.0).round() as u8)
+    }
+}
+
+/// CIE 1931 color matching functions, using the multi-lobe fit by Wyman, Sloan and Shirley.
+fn color_matching(wavelength: f64) -> [f64; 3]{
+    let lobe = |mean: f64, below: f64, above: f64| {
+        let t = (wavelength - mean) / if wavelength < mean { below } else { above };
+        (-0.5 * t * t).exp()
+    };
+    [
+        1.056 * lobe(599.8, 37.9, 31.0) + 0.362 * lobe(442.0, 16.0, 26.7) - 0.065 * lobe(501.1, 20.4, 26.2),
+        0.821 * lobe(568.8, 46.9, 40.5) + 0.286 * lobe(530.9, 16.3, 31.1),
+        1.217 * lobe(437.0, 11.8, 36.0) + 0.681 * lobe(459.0, 26.0, 13.8),
+    ]
+}
+
+#[test]
+fn doppler_shifted_colors() {
+    let white = Spectrum::default().observed_color(1.0).srgb();
+    assert!(white.iter().all(|c| (c - 1.0).abs() < 0.05));
+
+    // Approaching sources look bluer, receding ones redder.
+    let blue = Spectrum::default().observed_color(1.5).linear_rgb();
+    let red = Spectrum::default().observed_color(0.7).linear_rgb();
+    assert!(blue[2] > blue[0]);
+    assert!(red[0] > red[2]);
+
+    // A blackbody shifts to the blackbody at the scaled temperature.
+    let shifted = Spectrum::blackbody(3000.0).observed_color(2.0);
+    let hotter = Spectrum::blackbody(6000.0).observed_color(1.0);
+    let (s, h) = (shifted.get_xyz(), hotter.get_xyz());
+    assert!((s[0] / s[1] - h[0] / h[1]).abs() < 1e-9);
+    assert!((s[2] / s[1] - h[2] / h[1]).abs() < 1e-9);
+
+    // Green line light leaves the visible range when shifted far enough.
+    let line = Spectrum::sampled(vec![(520.0, 0.0), (530.0, 1.0), (540.0, 0.0)]);
+    assert!(line.observed_color(1.0).luminance() > 0.99);
+    assert_eq!(line.observed_color(0.5).luminance(), 0.0);
+    assert_eq!(line.observed_color(2.0).luminance(), 0.0);
+}
@@ -0,0 +1,91 @@
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+#[derive(Copy, Clone, Default, Debug, PartialEq)]
+pub struct Vector3D<T>{
+    pub x: T,
+    pub y: T,
+    pub z: T,
+}
+
+impl<T> Vector3D<T>{
+    pub const fn new(x: T, y: T, z: T) -> Self{
+        Self{
+            x,
+            y,
+            z,
+        }
+    }
+}
+
+impl<T> Add for Vector3D<T> where T: Add<T, Output = T>{
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
+    }
+}
+
+impl<T> Sub for Vector3D<T> where T: Sub<T, Output = T>{
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
+    }
+}
+
+impl<T> Mul<T> for Vector3D<T> where T: Mul<T, Output = T> + Copy{
+    type Output = Self;
+
+    fn mul(self, rhs: T) -> Self::Output {
+        Self::new(self.x * rhs, self.y * rhs, self.z * rhs)
+    }
+}
+
+impl<T> Div<T> for Vector3D<T> where T: Div<T, Output = T> + Copy{
+    type Output = Self;
+
+    fn div(self, rhs: T) -> Self::Output {
+        Self::new(self.x / rhs, self.y / rhs, self.z / rhs)
+    }
+}
+
+impl<T> Neg for Vector3D<T> where T: Neg<Output = T>{
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self::new(-self.x, -self.y, -self.z)
+    }
+}
+
+impl<T> Vector3D<T> where T: Mul<T, Output = T> + Add<T, Output = T> + Sub<T, Output = T> + Copy{
+    pub fn dot(v1: Self, v2: Self) -> T {
+        v1.x * v2.x + v1.y * v2.y + v1.z * v2.z
+    }
+
+    pub fn cross(v1: Self, v2: Self) -> Self {
+        Self::new(
+            v1.y * v2.z - v1.z * v2.y,
+            v1.z * v2.x - v1.x * v2.z,
+            v1.x * v2.y - v1.y * v2.x,
+        )
+    }
+
+    pub fn length_squared(self) -> T {
+        Self::dot(self, self)
+    }
+}
+
+impl Vector3D<f64>{
+    pub fn length(self) -> f64 {
+        self.length_squared().sqrt()
+    }
+
+    pub fn normalise(self) -> Self {
+        let len = self.length();
+        if len == 0.0 {
+            self
+        } else {
+            self / len
+        }
+    }
+}
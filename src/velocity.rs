@@ -0,0 +1,90 @@
+use vector2d::Vector2D;
+use crate::m_vector::MVector;
+use crate::spatial_vector::SpatialVector;
+use crate::MAX_SAFE_SPEED;
+
+pub fn gamma<V: SpatialVector>(velocity: V) -> f64{
+    1.0/(1.0 - velocity.length_squared()).sqrt()
+}
+
+/// Velocity in the base frame of something moving with `velocity_in_frame` relative to
+/// a frame that itself moves with `frame_velocity`.
+pub fn add_velocities<V: SpatialVector>(frame_velocity: V, velocity_in_frame: V) -> V{
+    let speed = frame_velocity.length();
+    if speed == 0.0 {
+        return clamp_speed(velocity_in_frame)
+    }
+    let u_direction = frame_velocity / speed;
+    let w_parallel = V::dot(u_direction, velocity_in_frame);
+    let w_perp = velocity_in_frame - u_direction * w_parallel;
+    let one_over_gamma = (1.0 - speed * speed).sqrt();
+    let res = (u_direction * (speed + w_parallel) + w_perp * one_over_gamma) / (1.0 + speed * w_parallel);
+    clamp_speed(res)
+}
+
+/// Velocity of `object_velocity` as measured in the rest frame of `observer_velocity`.
+pub fn relative_velocity<V: SpatialVector>(observer_velocity: V, object_velocity: V) -> V{
+    let four_velocity = MVector::new(1.0, object_velocity).lorentz_transform(observer_velocity);
+    clamp_speed(four_velocity.pos / four_velocity.time)
+}
+
+/// Inverse of `add_velocities`: the velocity that, added to `frame_velocity`, gives `velocity`.
+pub fn subtract_velocities<V: SpatialVector>(velocity: V, frame_velocity: V) -> V{
+    relative_velocity(frame_velocity, velocity)
+}
+
+fn clamp_speed<V: SpatialVector>(velocity: V) -> V{
+    if velocity.length_squared() >= 1.0 {
+        return velocity.normalise() * MAX_SAFE_SPEED
+    }
+    velocity
+}
+
+/// Rapidity vector: the direction of motion scaled by `atanh(speed)`.
+///
+/// Unlike velocities, rapidities of collinear motions add linearly.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Rapidity<V = Vector2D<f64>>(pub V);
+
+impl<V: SpatialVector> Rapidity<V>{
+    pub fn from_velocity(velocity: V) -> Self{
+        let speed = velocity.length();
+        if speed == 0.0 {
+            return Self(V::zero())
+        }
+        Self(velocity / speed * speed.min(MAX_SAFE_SPEED).atanh())
+    }
+
+    pub fn to_velocity(&self) -> V{
+        let magnitude = self.magnitude();
+        if magnitude == 0.0 {
+            return V::zero()
+        }
+        self.0 / magnitude * magnitude.tanh()
+    }
+
+    pub fn magnitude(&self) -> f64{
+        self.0.length()
+    }
+
+    pub fn gamma(&self) -> f64{
+        self.magnitude().cosh()
+    }
+
+    /// Rapidity reached by boosting by `self` and then by `other`, measured in the frame of `self`.
+    pub fn compose(&self, other: &Self) -> Self{
+        Self::from_velocity(add_velocities(self.to_velocity(), other.to_velocity()))
+    }
+}
+
+#[test]
+fn velocity_addition_round_trip() {
+    let u = Vector2D::new(0.7, -0.2);
+    let w = Vector2D::new(-0.3, 0.6);
+    let sum = add_velocities(u, w);
+
+    assert!(sum.length() < 1.0);
+    assert!((subtract_velocities(sum, u) - w).length() < 1e-9);
+    assert!((Rapidity::from_velocity(Vector2D::new(0.5, 0.0)).compose(&Rapidity::from_velocity(Vector2D::new(0.5, 0.0))).magnitude()
+        - 2.0 * 0.5f64.atanh()).abs() < 1e-9);
+}
@@ -0,0 +1,123 @@
+use std::collections::VecDeque;
+use vector2d::Vector2D;
+use crate::m_vector::MVector;
+use crate::motion::MotionSegment;
+use crate::spatial_vector::SpatialVector;
+
+/// State of an object at one event of its worldline.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct WorldlinePoint<V = Vector2D<f64>>{
+    m_pos: MVector<f64, V>,
+    velocity: V,
+    tau: f64,
+}
+
+impl<V: SpatialVector> WorldlinePoint<V>{
+    pub fn get_m_pos(&self) -> &MVector<f64, V> {
+        &self.m_pos
+    }
+
+    pub fn get_velocity(&self) -> &V {
+        &self.velocity
+    }
+
+    pub fn get_tau(&self) -> f64 {
+        self.tau
+    }
+}
+
+/// Bounded history of an object's motion as a chain of `MotionSegment`s.
+///
+/// Each segment is valid until the next one starts and the newest one is open-ended, so
+/// states between recorded changes are evaluated exactly rather than interpolated.
+#[derive(Clone, Debug)]
+pub struct Worldline<V = Vector2D<f64>>{
+    segments: VecDeque<MotionSegment<V>>,
+    capacity: usize,
+}
+
+impl<V: SpatialVector> Worldline<V>{
+    pub fn new(capacity: usize) -> Self{
+        Self{
+            segments: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+        }
+    }
+
+    pub fn get_capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn get_segments(&self) -> &VecDeque<MotionSegment<V>> {
+        &self.segments
+    }
+
+    /// Appends a segment, forgetting the oldest one when the capacity is exceeded.
+    pub fn push(&mut self, segment: MotionSegment<V>){
+        if let Some(last) = self.segments.back() && last.get_start_tau() >= segment.get_start_tau() {
+            self.segments.pop_back();
+        }
+        self.segments.push_back(segment);
+        while self.segments.len() > self.capacity {
+            self.segments.pop_front();
+        }
+    }
+
+    pub fn earliest_tau(&self) -> Option<f64>{
+        self.segments.front().map(|segment| segment.get_start_tau())
+    }
+
+    pub fn state_at_tau(&self, tau: f64) -> Option<WorldlinePoint<V>>{
+        let segment = self.segments.iter().rev().find(|segment| segment.get_start_tau() <= tau)?;
+        Some(Self::point(segment, tau))
+    }
+
+    pub fn state_at_time(&self, time: f64) -> Option<WorldlinePoint<V>>{
+        self.state_at_frame_time(time, V::zero())
+    }
+
+    /// State where the worldline crosses the simultaneity line of an observer at
+    /// `observer_event` moving with `observer_velocity`.
+    pub fn state_simultaneous_with(&self, observer_event: &MVector<f64, V>, observer_velocity: V) -> Option<WorldlinePoint<V>>{
+        self.state_at_frame_time(observer_event.lorentz_transform(observer_velocity).time, observer_velocity)
+    }
+
+    fn state_at_frame_time(&self, time: f64, frame_velocity: V) -> Option<WorldlinePoint<V>>{
+        let segment = self.segments.iter().rev()
+            .find(|segment| segment.get_start().lorentz_transform(frame_velocity).time <= time)?;
+        Some(Self::point(segment, segment.tau_at_frame_time(time, frame_velocity)))
+    }
+
+    fn point(segment: &MotionSegment<V>, tau: f64) -> WorldlinePoint<V>{
+        let (m_pos, velocity) = segment.state_at_tau(tau);
+        WorldlinePoint{
+            m_pos,
+            velocity,
+            tau,
+        }
+    }
+}
+
+#[test]
+fn worldline_queries_match_segments() {
+    let mut worldline = Worldline::new(2);
+    let first = MotionSegment::new(MVector::zero(), 0.0, Vector2D::new(0.6, 0.0), Vector2D::new(0.0, 0.0));
+    let (turn, _) = first.state_at_tau(1.0);
+    let second = MotionSegment::new(turn, 1.0, Vector2D::new(0.6, 0.0), Vector2D::new(0.0, 1.0));
+    worldline.push(first);
+    worldline.push(second);
+
+    let before_turn = worldline.state_at_time(0.5).unwrap_or_default();
+    assert!((before_turn.get_m_pos().pos.x - 0.3).abs() < 1e-9);
+    assert!((before_turn.get_tau() - 0.4).abs() < 1e-9);
+
+    let after_turn = worldline.state_at_tau(2.0).unwrap_or_default();
+    assert!((after_turn.get_m_pos().time - second.state_at_tau(2.0).0.time).abs() < 1e-9);
+
+    let observer_velocity = Vector2D::new(0.0, -0.5);
+    let simultaneous = worldline.state_simultaneous_with(&after_turn.m_pos, observer_velocity).unwrap_or_default();
+    assert!((simultaneous.get_tau() - 2.0).abs() < 1e-9);
+
+    worldline.push(MotionSegment::new(after_turn.m_pos, 2.0, after_turn.velocity, Vector2D::new(0.0, 0.0)));
+    assert!(worldline.state_at_time(0.5).is_none());
+}
@@ -1,9 +1,18 @@
+use std::f64::consts::PI;
 use vector2d::Vector2D;
+use rayon::iter::ParallelIterator;
+use minkowski_space::collision::CollisionResponse;
+use minkowski_space::intercept::{intercept_object, intercept_seen};
 use minkowski_space::m_frame::MFrame;
 use minkowski_space::m_vector::MVector;
+use minkowski_space::object_id::ObjectId;
+use minkowski_space::object_tracker::{ObjectTracker, PhotonQueueStats, Visibility};
+use minkowski_space::spectrum::Spectrum;
+use minkowski_space::DEFAULT_PHOTON_QUEUE_BUDGET;
+use minkowski_space::vector3d::Vector3D;
 
-fn test_minkowski_space(const_speed: bool){
-    let mut m_frame = MFrame::new();
+fn test_minkowski_space(const_speed: bool, update_ratio: f64){
+    let mut m_frame = MFrame::with_update_ratio(update_ratio);
     let transform = 0.5f64.sqrt();
     m_frame.get_frame_object_mut().set_velocity(Vector2D::new(0.8, 0.8) * transform);
     let id = m_frame.register_object(
@@ -45,10 +54,895 @@ fn test_minkowski_space(const_speed: bool){
 
 #[test]
 fn test_minkowski_space_const_v(){
-    test_minkowski_space(true)
+    test_minkowski_space(true, 1.0 / 120.0)
 }
 
 #[test]
 fn test_minkowski_space_non_const_v(){
-    test_minkowski_space(false)
-}
\ No newline at end of file
+    test_minkowski_space(false, 1.0 / 120.0)
+}
+
+#[test]
+fn test_minkowski_space_custom_update_ratio(){
+    for update_ratio in [1.0 / 30.0, 1.0 / 1000.0] {
+        test_minkowski_space(true, update_ratio);
+        test_minkowski_space(false, update_ratio);
+    }
+}
+fn accelerated_object_after(steps: &[f64]) -> (MVector<f64>, Vector2D<f64>, f64){
+    let mut m_frame = MFrame::new();
+    let id = m_frame.register_object(MVector::zero(), Vector2D::new(0.3, 0.0), false, 0.0);
+    m_frame.get_object_mut(&id).unwrap().set_acceleration(Vector2D::new(0.5, 1.5));
+    for delta in steps {
+        m_frame.process_time(*delta);
+    }
+    let object = &m_frame.get_object_with_properties(&id).unwrap().0;
+    (*object.get_m_pos(), *object.get_velocity(), object.get_tau())
+}
+
+#[test]
+fn test_acceleration_independent_of_step(){
+    let (fine_pos, fine_v, fine_tau) = accelerated_object_after(&[0.001; 3000]);
+    let (coarse_pos, coarse_v, coarse_tau) = accelerated_object_after(&[1.0, 0.25, 1.75]);
+
+    assert!((fine_pos.time - 3.0).abs() < 1e-9);
+    assert!((coarse_pos.time - 3.0).abs() < 1e-9);
+    assert!((fine_pos.pos - coarse_pos.pos).length() < 1e-9);
+    assert!((fine_v - coarse_v).length() < 1e-9);
+    assert!((fine_tau - coarse_tau).abs() < 1e-9);
+}
+
+#[test]
+fn test_minkowski_space_3d(){
+    let mut m_frame = MFrame::<Vector3D<f64>>::new();
+    let direction = Vector3D::new(1.0, 1.0, 1.0).normalise();
+    m_frame.get_frame_object_mut().set_velocity(direction * 0.8);
+    let id = m_frame.register_object(MVector::new(0.0, direction * 2.0), direction * -0.6, false, 0.1);
+
+    for _i in 0..60 {
+        m_frame.process_time(0.01);
+    }
+    assert!(!m_frame.get_object_with_properties(&id).unwrap().1.get_object_was_seen());
+
+    for _i in 0..15 {
+        m_frame.process_time(0.01);
+    }
+    let tracker = &m_frame.get_object_with_properties(&id).unwrap().1;
+    assert!(tracker.get_object_was_seen());
+    assert!((tracker.get_visible_m_vector().time - 5.0/8.0).abs() < 1e-6);
+    assert!((tracker.get_relative_frequency() - 6.0).abs() < 1e-6);
+    assert_eq!(tracker.get_basis().len(), 3);
+    assert!(tracker.get_basis_z().length() > 0.0);
+}
+
+#[test]
+fn test_object_energy_in_frames(){
+    let mut m_frame = MFrame::new();
+    let id = m_frame.register_object(MVector::zero(), Vector2D::new(0.8, 0.0), true, 0.0);
+    m_frame.get_object_mut(&id).unwrap().set_rest_mass(3.0);
+    let object = &m_frame.get_object_with_properties(&id).unwrap().0;
+
+    assert!((object.energy() - 5.0).abs() < 1e-9);
+    assert!((object.momentum().x - 4.0).abs() < 1e-9);
+    assert!((object.kinetic_energy() - 2.0).abs() < 1e-9);
+
+    let in_rest_frame = object.four_momentum_in_frame(*object.get_velocity());
+    assert!((in_rest_frame.energy() - 3.0).abs() < 1e-9);
+    assert!((in_rest_frame.kinetic_energy()).abs() < 1e-9);
+}
+
+#[test]
+fn test_collision_detection(){
+    let mut m_frame = MFrame::new();
+    let first = m_frame.register_object(MVector::new(0.0, Vector2D::new(-1.0, 0.0)), Vector2D::new(0.5, 0.0), true, 0.1);
+    let second = m_frame.register_object(MVector::new(0.0, Vector2D::new(1.0, 0.0)), Vector2D::new(-0.5, 0.0), false, 0.1);
+    m_frame.register_object(MVector::new(0.0, Vector2D::new(-1.0, 0.5)), Vector2D::new(0.5, 0.0), true, 0.1);
+
+    let mut events = vec![];
+    for _i in 0..250 {
+        m_frame.process_time(0.01);
+        events.extend_from_slice(m_frame.get_collision_events());
+    }
+
+    assert_eq!(events.len(), 1);
+    assert_eq!((events[0].get_first(), events[0].get_second()), (first, second));
+    assert!((events[0].get_event().time - 1.8).abs() < 1e-6);
+    assert!(events[0].get_event().pos.length() < 1e-6);
+}
+
+#[test]
+fn test_lasting_contact_between_fast_objects(){
+    let mut m_frame = MFrame::new();
+    m_frame.register_object(MVector::new(0.0, Vector2D::new(0.0, 0.0)), Vector2D::new(0.9, 0.0), true, 0.1);
+    m_frame.register_object(MVector::new(0.0, Vector2D::new(0.05, 0.0)), Vector2D::new(0.91, 0.0), true, 0.1);
+
+    let mut events = 0;
+    for _i in 0..600 {
+        m_frame.process_time(0.01);
+        events += m_frame.get_collision_events().len();
+    }
+
+    // The objects touch from the start and drift apart slowly; that is a single contact.
+    assert_eq!(events, 1);
+}
+
+fn collide_head_on(collision_response: CollisionResponse, second_mass: f64) -> (MFrame, ObjectId, ObjectId){
+    let mut m_frame = MFrame::new();
+    m_frame.set_collision_response(collision_response);
+    let first = m_frame.register_object(MVector::new(0.0, Vector2D::new(-1.0, 0.0)), Vector2D::new(0.5, 0.0), true, 0.1);
+    let second = m_frame.register_object(MVector::new(0.0, Vector2D::new(1.0, 0.05)), Vector2D::new(-0.5, 0.0), false, 0.1);
+    m_frame.get_object_mut(&second).unwrap().set_rest_mass(second_mass);
+    (m_frame, first, second)
+}
+
+#[test]
+fn test_elastic_collision_conserves_four_momentum(){
+    let (mut m_frame, first, second) = collide_head_on(CollisionResponse::Elastic, 3.0);
+    let total_momentum = |m_frame: &MFrame| {
+        let p = m_frame.get_object_with_properties(&first).unwrap().0.four_momentum()
+            + m_frame.get_object_with_properties(&second).unwrap().0.four_momentum();
+        *p.get_m_vector()
+    };
+    let before = total_momentum(&m_frame);
+    for _i in 0..500 {
+        m_frame.process_time(0.01);
+    }
+    let after = total_momentum(&m_frame);
+
+    assert!((before.time - after.time).abs() < 1e-9);
+    assert!((before.pos - after.pos).length() < 1e-9);
+    let first_object = &m_frame.get_object_with_properties(&first).unwrap().0;
+    assert!(first_object.get_velocity().x < 0.0);
+    assert!(!first_object.constant_velocity());
+
+    let tracker = &m_frame.get_object_with_properties(&first).unwrap().1;
+    assert!(tracker.get_visible_m_vector().time > 1.8);
+    assert!(tracker.get_visible_m_vector().pos.x < -0.1);
+}
+
+#[test]
+fn test_collision_detection_can_be_turned_off(){
+    let (mut m_frame, first, _) = collide_head_on(CollisionResponse::Elastic, 1.0);
+    assert!(m_frame.get_collision_detection());
+    m_frame.set_collision_detection(false);
+    for _i in 0..500 {
+        m_frame.process_time(0.01);
+        assert!(m_frame.get_collision_events().is_empty());
+    }
+    // The objects passed through each other.
+    assert!((m_frame.get_object_with_properties(&first).unwrap().0.get_velocity().x - 0.5).abs() < 1e-9);
+}
+
+#[test]
+fn test_accelerating_objects_bounce_at_contact(){
+    let mut m_frame = MFrame::new();
+    m_frame.set_collision_response(CollisionResponse::Elastic);
+    let first = m_frame.register_object(MVector::new(0.0, Vector2D::new(-1.0, 0.0)), Vector2D::new(0.0, 0.0), false, 0.1);
+    let second = m_frame.register_object(MVector::new(0.0, Vector2D::new(1.0, 0.0)), Vector2D::new(0.0, 0.0), false, 0.1);
+    m_frame.get_object_mut(&first).unwrap().set_acceleration(Vector2D::new(1.0, 0.0));
+    m_frame.get_object_mut(&second).unwrap().set_acceleration(Vector2D::new(-1.0, 0.0));
+    let mut events = vec![];
+    while events.is_empty() {
+        m_frame.process_time(0.1);
+        events.extend_from_slice(m_frame.get_collision_events());
+    }
+
+    // Hyperbolic motion reaches x = -0.1 at t = sqrt(1.9^2 - 1), in the middle of a step.
+    let contact_time = (1.9f64 * 1.9 - 1.0).sqrt();
+    assert_eq!(events.len(), 1);
+    assert!((events[0].get_event().time - contact_time).abs() < 1e-9);
+    assert!((events[0].get_first_center().pos.x + 0.1).abs() < 1e-9);
+
+    // Both bounce back at the contact with the speed they had there, and do not overlap.
+    let contact_speed = contact_time / 1.9;
+    let (first_object, second_object) = (&m_frame.get_object_with_properties(&first).unwrap().0, &m_frame.get_object_with_properties(&second).unwrap().0);
+    let segment = first_object.get_motion_segment();
+    assert!((segment.get_start().time - contact_time).abs() < 1e-9);
+    assert!((segment.get_start_velocity().x + contact_speed).abs() < 1e-9);
+    assert!(second_object.get_m_pos().pos.x - first_object.get_m_pos().pos.x > 0.2);
+}
+
+#[test]
+fn test_inelastic_collision_merges_objects(){
+    let (mut m_frame, first, second) = collide_head_on(CollisionResponse::Inelastic, 1.0);
+    let mut merged = None;
+    for _i in 0..250 {
+        m_frame.process_time(0.01);
+        let Some(event) = m_frame.get_collision_events().first() else {
+            continue
+        };
+        merged = event.get_merged_into();
+        // The originals end at the contact, not at the end of the step.
+        for original in [first, second] {
+            let (_, tracker) = m_frame.get_destroyed_object(&original).unwrap();
+            let separation = *tracker.get_destruction_event().unwrap() - *event.get_event();
+            assert!(separation.time.abs() < 1e-12 && separation.pos.length() < 1e-12);
+        }
+    }
+
+    let merged = merged.unwrap();
+    assert!(m_frame.get_object_with_properties(&first).is_none());
+    assert!(m_frame.get_object_with_properties(&second).is_none());
+    let merged_object = &m_frame.get_object_with_properties(&merged).unwrap().0;
+    assert!((merged_object.get_rest_mass() - 4.0 / 3.0f64.sqrt()).abs() < 1e-9);
+    assert!(merged_object.get_velocity().length() < 1e-9);
+    assert!((merged_object.get_m_pos().time - 2.5).abs() < 1e-9);
+}
+
+#[test]
+fn test_worldline_history(){
+    let mut m_frame = MFrame::new();
+    let id = m_frame.register_object(MVector::zero(), Vector2D::new(0.6, 0.0), false, 0.0);
+    m_frame.get_object_mut(&id).unwrap().enable_history(4);
+    for _i in 0..100 {
+        m_frame.process_time(0.01);
+    }
+    m_frame.get_object_mut(&id).unwrap().set_acceleration(Vector2D::new(0.0, 1.0));
+    for _i in 0..100 {
+        m_frame.process_time(0.01);
+    }
+    m_frame.get_object_mut(&id).unwrap().set_acceleration(Vector2D::new(0.0, 0.0));
+    for _i in 0..50 {
+        m_frame.process_time(0.01);
+    }
+
+    let object = &m_frame.get_object_with_properties(&id).unwrap().0;
+    assert_eq!(object.get_history().unwrap().get_segments().len(), 3);
+
+    let early = object.history_at_time(0.5).unwrap();
+    assert!((early.get_m_pos().pos.x - 0.3).abs() < 1e-9);
+    assert!((early.get_tau() - 0.4).abs() < 1e-9);
+
+    let now = object.history_at_tau(object.get_tau()).unwrap();
+    assert!((now.get_m_pos().pos - object.get_m_pos().pos).length() < 1e-9);
+    assert!((*now.get_velocity() - *object.get_velocity()).length() < 1e-9);
+
+    let seen_from_start = object.history_simultaneous_with(&MVector::new(1.5, Vector2D::new(0.0, 0.0)), Vector2D::new(0.0, 0.0)).unwrap();
+    assert!((seen_from_start.get_m_pos().time - 1.5).abs() < 1e-9);
+    assert!(object.history_at_time(object.get_m_pos().time + 1.0).is_none());
+}
+
+fn visible_accelerated_object(update_ratio: f64) -> Vec<(MVector<f64>, f64)>{
+    let mut m_frame = MFrame::with_update_ratio(update_ratio);
+    m_frame.get_frame_object_mut().set_velocity(Vector2D::new(0.0, 0.4));
+    let id = m_frame.register_object(MVector::new(0.0, Vector2D::new(3.0, 0.0)), Vector2D::new(0.0, -0.2), false, 0.1);
+    m_frame.get_object_mut(&id).unwrap().set_acceleration(Vector2D::new(-0.8, 0.3));
+    let mut seen = vec![];
+    for _i in 0..700 {
+        m_frame.process_time(0.01);
+        let receiver = *m_frame.get_frame_object_mut().get_m_pos();
+        let tracker = &m_frame.get_object_with_properties(&id).unwrap().1;
+        if tracker.get_object_was_seen() {
+            let visible = *tracker.get_visible_m_vector();
+            assert!((receiver - visible).length_squared().abs() < 1e-9);
+            assert!(receiver.time > visible.time);
+            seen.push((visible, tracker.get_relative_frequency()));
+        }
+    }
+    seen
+}
+
+#[test]
+fn test_visible_position_independent_of_photon_rate(){
+    let coarse = visible_accelerated_object(0.5);
+    let fine = visible_accelerated_object(1.0 / 1000.0);
+    assert_eq!(coarse.len(), fine.len());
+    assert!(!coarse.is_empty());
+    for ((coarse_pos, coarse_freq), (fine_pos, fine_freq)) in coarse.iter().zip(&fine) {
+        assert!((coarse_pos.time - fine_pos.time).abs() < 1e-9);
+        assert!((coarse_pos.pos - fine_pos.pos).length() < 1e-9);
+        assert!((coarse_freq - fine_freq).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn test_multiple_observers(){
+    let mut m_frame = MFrame::new();
+    let id = m_frame.register_object(MVector::new(0.0, Vector2D::new(3.0, 0.0)), Vector2D::new(-0.6, 0.0), true, 0.1);
+    let observer = m_frame.register_object(MVector::new(0.0, Vector2D::new(6.0, 0.0)), Vector2D::new(0.0, 0.0), false, 0.0);
+    assert!(m_frame.add_observer(&observer));
+    assert!(!m_frame.add_observer(&MFrame::<Vector2D<f64>>::new().register_object(MVector::zero(), Vector2D::new(0.0, 0.0), true, 0.0)));
+    assert!(m_frame.get_observed_properties(&observer, &observer).is_none());
+
+    for _i in 0..400 {
+        m_frame.process_time(0.01);
+    }
+
+    let receiver = *m_frame.get_object_with_properties(&observer).unwrap().0.get_m_pos();
+    let seen_by_observer = m_frame.get_observed_properties(&observer, &id).unwrap();
+    assert!(seen_by_observer.get_object_was_seen());
+    assert!((receiver - *seen_by_observer.get_visible_m_vector()).length_squared().abs() < 1e-9);
+    assert!((seen_by_observer.get_relative_frequency() - 0.5).abs() < 1e-9);
+
+    let seen_by_frame = &m_frame.get_object_with_properties(&id).unwrap().1;
+    assert!((seen_by_frame.get_relative_frequency() - 2.0).abs() < 1e-9);
+
+    m_frame.unregister_object(&observer);
+    assert_eq!(m_frame.get_observers().count(), 0);
+}
+
+#[test]
+fn test_switch_observer(){
+    let mut m_frame = MFrame::new();
+    m_frame.get_frame_object_mut().set_velocity(Vector2D::new(0.0, 0.5));
+    let id = m_frame.register_object(MVector::new(0.0, Vector2D::new(2.0, 0.0)), Vector2D::new(0.0, 0.3), true, 0.1);
+    let ship = m_frame.register_object(MVector::new(0.0, Vector2D::new(-1.0, 0.0)), Vector2D::new(0.4, 0.0), false, 0.1);
+    m_frame.add_observer(&ship);
+    for _i in 0..300 {
+        m_frame.process_time(0.01);
+    }
+    let seen_from_ship = *m_frame.get_observed_properties(&ship, &id).unwrap().get_visible_m_vector();
+
+    let previous = m_frame.switch_observer(&ship).unwrap();
+    assert!(m_frame.get_object_with_properties(&ship).is_none());
+    assert!(m_frame.switch_observer(&ship).is_none());
+    let tracker = &m_frame.get_object_with_properties(&id).unwrap().1;
+    assert!(tracker.get_object_was_seen());
+    assert!((*tracker.get_visible_m_vector() - seen_from_ship).length_squared().abs() < 1e-12);
+
+    let (previous_observer, previous_tracker, _) = m_frame.get_object_with_properties(&previous).unwrap();
+    assert!((previous_observer.get_velocity().y - 0.5).abs() < 1e-9);
+    assert!(previous_tracker.get_object_was_seen());
+
+    m_frame.get_frame_object_mut().set_velocity(Vector2D::new(-0.2, 0.0));
+    for _i in 0..100 {
+        m_frame.process_time(0.01);
+    }
+    let receiver = *m_frame.get_frame_object_mut().get_m_pos();
+    let previous_tracker = &m_frame.get_object_with_properties(&previous).unwrap().1;
+    assert!((receiver - *previous_tracker.get_visible_m_vector()).length_squared().abs() < 1e-9);
+
+    // A constant-velocity object can be steered once it is the observer.
+    m_frame.switch_observer(&id).unwrap();
+    let frame_object = m_frame.get_frame_object_mut();
+    assert!(!frame_object.constant_velocity());
+    frame_object.set_velocity(Vector2D::new(0.0, 0.5));
+    assert!((frame_object.get_velocity().y - 0.5).abs() < 1e-9);
+    frame_object.set_acceleration(Vector2D::new(0.0, 0.5));
+    assert!((frame_object.get_acceleration().y - 0.5).abs() < 1e-9);
+    m_frame.process_time(0.1);
+    assert!(m_frame.get_frame_object().get_velocity().y > 0.5);
+}
+
+/// A mover and the frame object change course near the origin, then `far` at x = 20 is made
+/// an observer before any of that light reaches it.
+fn far_observer_after_course_changes() -> (MFrame, ObjectId, ObjectId){
+    let mut m_frame = MFrame::new();
+    let mover = m_frame.register_object(MVector::new(0.0, Vector2D::new(0.0, 1.0)), Vector2D::new(0.1, 0.0), false, 0.1);
+    let far = m_frame.register_object(MVector::new(0.0, Vector2D::new(20.0, 0.0)), Vector2D::new(0.0, 0.0), false, 0.0);
+    for i in 0..300 {
+        match i {
+            100 => m_frame.get_object_mut(&mover).unwrap().set_velocity(Vector2D::new(0.3, 0.0)),
+            150 => m_frame.get_frame_object_mut().set_velocity(Vector2D::new(0.0, 0.2)),
+            200 => m_frame.get_object_mut(&mover).unwrap().set_velocity(Vector2D::new(-0.2, 0.0)),
+            _ => {}
+        }
+        m_frame.process_time(0.01);
+    }
+    (m_frame, mover, far)
+}
+
+fn record_seen_velocity(seen: &mut Vec<Vector2D<f64>>, tracker: &ObjectTracker){
+    let Some(segment) = tracker.get_visible_segment().filter(|_| tracker.get_object_was_seen()) else {
+        return
+    };
+    let velocity = *segment.get_start_velocity();
+    if seen.last().is_none_or(|last| (*last - velocity).length() > 1e-9) {
+        seen.push(velocity);
+    }
+}
+
+fn assert_course_changes_seen(seen: &[Vec<Vector2D<f64>>; 2]){
+    let expected = [
+        vec![Vector2D::new(0.1, 0.0), Vector2D::new(0.3, 0.0), Vector2D::new(-0.2, 0.0)],
+        vec![Vector2D::new(0.0, 0.0), Vector2D::new(0.0, 0.2)],
+    ];
+    for (seen, expected) in seen.iter().zip(&expected) {
+        assert_eq!(seen.len(), expected.len());
+        assert!(seen.iter().zip(expected).all(|(a, b)| (*a - *b).length() < 1e-9));
+    }
+}
+
+#[test]
+fn test_switch_to_far_observer(){
+    let (mut m_frame, mover, far) = far_observer_after_course_changes();
+    let previous = m_frame.switch_observer(&far).unwrap();
+    let mut seen = [vec![], vec![]];
+    for _i in 0..2200 {
+        m_frame.process_time(0.01);
+        record_seen_velocity(&mut seen[0], &m_frame.get_object_with_properties(&mover).unwrap().1);
+        record_seen_velocity(&mut seen[1], &m_frame.get_object_with_properties(&previous).unwrap().1);
+    }
+    assert_course_changes_seen(&seen);
+}
+
+#[test]
+fn test_add_far_observer(){
+    let (mut m_frame, mover, far) = far_observer_after_course_changes();
+    m_frame.add_observer(&far);
+    let mut seen = [vec![], vec![]];
+    for _i in 0..2200 {
+        m_frame.process_time(0.01);
+        record_seen_velocity(&mut seen[0], m_frame.get_observed_properties(&far, &mover).unwrap());
+        record_seen_velocity(&mut seen[1], m_frame.get_observed_frame_object(&far).unwrap());
+    }
+    assert_course_changes_seen(&seen);
+}
+
+#[test]
+fn test_frame_object_visible_to_observers(){
+    let mut m_frame = MFrame::new();
+    let sensor = m_frame.register_object(MVector::new(0.0, Vector2D::new(3.0, 0.0)), Vector2D::new(0.0, 0.0), false, 0.0);
+    assert!(m_frame.get_observed_frame_object(&sensor).is_none());
+    m_frame.add_observer(&sensor);
+    // The frame object has no creation event, so it exists for every observer from the start.
+    assert_eq!(m_frame.get_observed_frame_object(&sensor).unwrap().get_visibility(), Visibility::Visible);
+    m_frame.get_frame_object_mut().set_velocity(Vector2D::new(-0.6, 0.0));
+    for _i in 0..400 {
+        m_frame.process_time(0.01);
+    }
+
+    let receiver = *m_frame.get_object_with_properties(&sensor).unwrap().0.get_m_pos();
+    let player = m_frame.get_observed_frame_object(&sensor).unwrap();
+    assert!(player.get_object_was_seen());
+    assert!((receiver - *player.get_visible_m_vector()).length_squared().abs() < 1e-9);
+    assert!((player.get_relative_frequency() - 0.5).abs() < 1e-9);
+    let seen_player = *player.get_visible_m_vector();
+
+    let previous = m_frame.switch_observer(&sensor).unwrap();
+    let previous_tracker = &m_frame.get_object_with_properties(&previous).unwrap().1;
+    assert!((*previous_tracker.get_visible_m_vector() - seen_player).length_squared().abs() < 1e-12);
+    assert_eq!(previous_tracker.get_visibility(), Visibility::Visible);
+}
+
+#[test]
+fn test_stale_object_ids(){
+    let mut m_frame = MFrame::new();
+    let first = m_frame.register_object(MVector::zero(), Vector2D::new(0.1, 0.0), true, 0.1);
+    m_frame.unregister_object(&first);
+    let second = m_frame.register_object(MVector::zero(), Vector2D::new(0.2, 0.0), true, 0.1);
+    assert_eq!(first.get_index(), second.get_index());
+    assert!(!m_frame.contains_object(&first));
+    assert!(m_frame.get_object_with_properties(&first).is_none());
+    assert!(m_frame.get_object_mut(&first).is_none());
+
+    m_frame.unregister_object(&first);
+    assert!(m_frame.contains_object(&second));
+
+    let mut other_frame = MFrame::<Vector2D<f64>>::new();
+    let other = other_frame.register_object(MVector::zero(), Vector2D::new(0.0, 0.0), true, 0.1);
+    assert_eq!(other.get_index(), second.get_index());
+    assert!(!m_frame.contains_object(&other));
+}
+
+#[test]
+fn test_iterate_objects(){
+    let mut m_frame = MFrame::new();
+    let near = m_frame.register_object(MVector::new(0.0, Vector2D::new(0.5, 0.0)), Vector2D::new(0.0, 0.0), true, 0.1);
+    let far = m_frame.register_object(MVector::new(0.0, Vector2D::new(50.0, 0.0)), Vector2D::new(0.0, 0.0), true, 0.1);
+    let observer = m_frame.register_object(MVector::new(0.0, Vector2D::new(49.0, 0.0)), Vector2D::new(0.0, 0.0), false, 0.0);
+    m_frame.add_observer(&observer);
+    for _i in 0..100 {
+        m_frame.process_time(0.01);
+    }
+    assert_eq!(m_frame.len(), 3);
+    assert_eq!(m_frame.iter().count(), 3);
+    let seen: Vec<ObjectId> = m_frame.iter_seen().map(|(id, _, _, _)| *id).collect();
+    assert_eq!(seen, vec![near]);
+    let seen_by_observer: Vec<ObjectId> = m_frame.iter_observed(&observer)
+        .filter(|(_, _, tracker, _)| tracker.get_object_was_seen())
+        .map(|(id, _, _, _)| *id)
+        .collect();
+    assert_eq!(seen_by_observer, vec![far]);
+
+    m_frame.par_iter_mut().for_each(|(_, object, _, _)| object.set_acceleration(Vector2D::new(0.0, 0.1)));
+    let accelerating = m_frame.par_iter().filter(|(_, object, _, _)| object.get_acceleration().y > 0.0).count();
+    assert_eq!(accelerating, 1);
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Entity{
+    name: String,
+    hits: u32,
+}
+
+#[test]
+fn test_object_payloads(){
+    let mut m_frame = MFrame::with_frame_data(1.0 / 120.0, Entity{ name: "player".into(), hits: 0 });
+    let ship = m_frame.register_object_with_data(MVector::new(0.0, Vector2D::new(1.0, 0.0)), Vector2D::new(0.0, 0.0), false, 0.1, m_frame.get_update_ratio(), Entity{ name: "ship".into(), hits: 0 });
+    let rock = m_frame.register_object(MVector::new(0.0, Vector2D::new(-1.0, 0.0)), Vector2D::new(0.0, 0.0), true, 0.1);
+    assert_eq!(m_frame.get_data(&rock), Some(&Entity::default()));
+
+    m_frame.iter_mut().for_each(|(_, _, _, entity)| entity.hits += 1);
+    m_frame.get_data_mut(&ship).unwrap().hits += 1;
+    assert_eq!(m_frame.get_object_with_properties(&ship).unwrap().2.hits, 2);
+
+    let previous = m_frame.switch_observer(&ship).unwrap();
+    assert_eq!(m_frame.get_frame_data().name, "ship");
+    assert_eq!(m_frame.get_data(&previous).unwrap().name, "player");
+
+    let removed = m_frame.unregister_object(&rock).unwrap();
+    assert_eq!(removed.hits, 1);
+    assert!(m_frame.unregister_object(&rock).is_none());
+}
+
+#[test]
+fn test_merged_payloads_are_kept(){
+    let mut m_frame: MFrame<Vector2D<f64>, Entity> = MFrame::default();
+    m_frame.set_collision_response(CollisionResponse::Inelastic);
+    let first = m_frame.register_object_with_data(MVector::new(0.0, Vector2D::new(-1.0, 0.0)), Vector2D::new(0.5, 0.0), true, 0.1, m_frame.get_update_ratio(), Entity{ name: "first".into(), hits: 0 });
+    let second = m_frame.register_object_with_data(MVector::new(0.0, Vector2D::new(1.0, 0.0)), Vector2D::new(-0.5, 0.0), true, 0.1, m_frame.get_update_ratio(), Entity{ name: "second".into(), hits: 0 });
+    let mut merged = None;
+    for _i in 0..250 {
+        m_frame.process_time(0.01);
+        merged = merged.or(m_frame.get_collision_events().first().and_then(|e| e.get_merged_into()));
+    }
+
+    assert_eq!(m_frame.get_data(&merged.unwrap()).unwrap().name, "first");
+    let absorbed = m_frame.take_merged_payloads();
+    assert_eq!(absorbed.len(), 1);
+    assert_eq!(absorbed[0].0, second);
+    assert_eq!(absorbed[0].1.name, "second");
+    assert!(m_frame.take_merged_payloads().is_empty());
+    assert!(m_frame.get_data(&first).is_none());
+}
+
+#[test]
+fn test_destroyed_object_lingers_until_light_arrives(){
+    let mut m_frame = MFrame::new();
+    let id = m_frame.register_object(MVector::new(0.0, Vector2D::new(1.0, 0.0)), Vector2D::new(0.0, 0.0), true, 0.1);
+    let observer = m_frame.register_object(MVector::new(0.0, Vector2D::new(4.0, 0.0)), Vector2D::new(0.0, 0.0), false, 0.0);
+    m_frame.add_observer(&observer);
+    for _i in 0..200 {
+        m_frame.process_time(0.01);
+    }
+    assert!(m_frame.unregister_object(&id).is_some());
+    assert!(!m_frame.contains_object(&id));
+
+    // Destroyed at t = 2: the frame object sees it at t = 3, the observer at t = 5.
+    let mut frame_saw_destruction_at = None;
+    let mut observer_saw_destruction_at = None;
+    for _i in 0..400 {
+        m_frame.process_time(0.01);
+        let time = m_frame.get_frame_object_mut().get_m_pos().time;
+        if let Some((_, tracker)) = m_frame.get_destroyed_object(&id) {
+            if tracker.get_object_was_destroyed() {
+                frame_saw_destruction_at.get_or_insert(time);
+            } else {
+                assert!(tracker.get_object_was_seen());
+                assert!(tracker.get_visible_m_vector().time < 2.0 + 1e-9);
+            }
+        }
+        if let Some(tracker) = m_frame.get_observed_properties(&observer, &id) && tracker.get_object_was_destroyed() {
+            observer_saw_destruction_at.get_or_insert(time);
+        }
+    }
+    assert!((frame_saw_destruction_at.unwrap() - 3.0).abs() < 0.015);
+    assert!((observer_saw_destruction_at.unwrap() - 5.0).abs() < 0.015);
+    assert!(m_frame.get_destroyed_object(&id).is_none());
+    assert!(m_frame.get_observed_properties(&observer, &id).is_none());
+    assert_eq!(m_frame.iter_destroyed().count(), 0);
+}
+
+#[test]
+fn test_spawned_object_appears_when_creation_light_arrives(){
+    let mut m_frame = MFrame::new();
+    for _i in 0..100 {
+        m_frame.process_time(0.01);
+    }
+    let creation = MVector::new(0.5, Vector2D::new(0.0, 3.0));
+    let id = m_frame.register_object(creation, Vector2D::new(0.5, 0.0), true, 0.1);
+    let observer = m_frame.register_object(MVector::new(1.0, Vector2D::new(0.0, 2.0)), Vector2D::new(0.0, 0.0), false, 0.0);
+    m_frame.add_observer(&observer);
+
+    let tracker = &m_frame.get_object_with_properties(&id).unwrap().1;
+    assert_eq!(tracker.get_visibility(), Visibility::NotYetCreated);
+    assert_eq!(tracker.get_creation_event().map(|e| e.time), Some(0.5));
+
+    let mut frame_saw_creation_at = None;
+    let mut observer_saw_creation_at = None;
+    for _i in 0..300 {
+        m_frame.process_time(0.01);
+        let time = m_frame.get_frame_object_mut().get_m_pos().time;
+        if m_frame.get_object_with_properties(&id).unwrap().1.get_visibility() == Visibility::Visible {
+            frame_saw_creation_at.get_or_insert(time);
+        }
+        if m_frame.get_observed_properties(&observer, &id).unwrap().get_visibility() == Visibility::Visible {
+            observer_saw_creation_at.get_or_insert(time);
+        }
+    }
+    assert!((frame_saw_creation_at.unwrap() - 3.5).abs() < 0.015);
+    assert!((observer_saw_creation_at.unwrap() - 1.5).abs() < 0.015);
+
+    m_frame.unregister_object(&id);
+    for _i in 0..400 {
+        m_frame.process_time(0.01);
+        if let Some((_, tracker)) = m_frame.get_destroyed_object(&id) && tracker.get_object_was_destroyed() {
+            assert_eq!(tracker.get_visibility(), Visibility::Destroyed);
+        }
+    }
+}
+
+#[test]
+fn test_spawn_projectile_relative_to_shooter(){
+    let mut m_frame = MFrame::new();
+    let ship = m_frame.register_object(MVector::new(0.0, Vector2D::new(1.0, 0.0)), Vector2D::new(0.9, 0.0), true, 0.1);
+    for _i in 0..50 {
+        m_frame.process_time(0.01);
+    }
+    let forward = m_frame.spawn_projectile(&ship, Vector2D::new(0.5, 0.0), true, 0.01).unwrap();
+    let sideways = m_frame.spawn_projectile(&ship, Vector2D::new(0.0, 0.5), true, 0.01).unwrap();
+    let ship_event = *m_frame.get_object_with_properties(&ship).unwrap().0.get_m_pos();
+
+    let (forward_object, forward_tracker, _) = m_frame.get_object_with_properties(&forward).unwrap();
+    assert!((forward_object.get_velocity().x - 1.4 / 1.45).abs() < 1e-9);
+    assert!((*forward_object.get_m_pos() - ship_event).length_squared().abs() < 1e-12);
+    assert_eq!(forward_tracker.get_creation_event().map(|e| e.time), Some(ship_event.time));
+    let sideways_velocity = *m_frame.get_object_with_properties(&sideways).unwrap().0.get_velocity();
+    assert!((sideways_velocity.x - 0.9).abs() < 1e-9);
+    assert!((sideways_velocity.y - 0.5 * 0.19f64.sqrt()).abs() < 1e-9);
+
+    for _i in 0..50 {
+        m_frame.process_time(0.01);
+        assert!(m_frame.get_collision_events().iter().all(|e| e.get_first() != ship && e.get_second() != ship));
+    }
+
+    let from_player = m_frame.spawn_projectile_from_frame_object(Vector2D::new(0.0, -0.3), true, 0.0);
+    assert!((m_frame.get_object_with_properties(&from_player).unwrap().0.get_velocity().y + 0.3).abs() < 1e-9);
+    assert!(m_frame.spawn_projectile(&from_player, Vector2D::new(0.1, 0.0), true, 0.0).is_some());
+    m_frame.unregister_object(&ship);
+    assert!(m_frame.spawn_projectile(&ship, Vector2D::new(0.1, 0.0), true, 0.0).is_none());
+}
+
+#[test]
+fn test_intercept_moving_shooter_and_accelerating_target(){
+    let mut m_frame = MFrame::new();
+    let shooter = m_frame.register_object(MVector::zero(), Vector2D::new(0.7, 0.0), false, 0.05);
+    let target = m_frame.register_object(MVector::new(0.0, Vector2D::new(1.0, 2.0)), Vector2D::new(0.3, 0.1), false, 0.005);
+    m_frame.get_object_mut(&target).unwrap().set_acceleration(Vector2D::new(0.2, -0.1));
+    for _i in 0..10 {
+        m_frame.process_time(0.01);
+    }
+    let solution = {
+        let shooter_object = &m_frame.get_object_with_properties(&shooter).unwrap().0;
+        let target_object = &m_frame.get_object_with_properties(&target).unwrap().0;
+        intercept_object(shooter_object, target_object, 0.8).unwrap()
+    };
+    let projectile = m_frame.spawn_projectile(&shooter, *solution.get_launch_velocity(), true, 0.0).unwrap();
+    assert!((*m_frame.get_object_with_properties(&projectile).unwrap().0.get_velocity() - *solution.get_base_velocity()).length() < 1e-9);
+
+    let mut hit = None;
+    while hit.is_none() && m_frame.get_frame_object().get_m_pos().time < 20.0 {
+        m_frame.process_time(0.01);
+        hit = m_frame.get_collision_events().iter()
+            .find(|e| (e.get_first(), e.get_second()) == (target, projectile))
+            .map(|e| *e.get_event());
+    }
+    let hit = hit.unwrap();
+    // The projectile is a point, so it meets the target's surface just before the center.
+    assert!((hit.time - solution.get_event().time).abs() < 0.02);
+    assert!((hit.pos - solution.get_event().pos).length() < 0.02);
+}
+
+#[test]
+fn test_intercept_seen_by_shooter(){
+    let mut m_frame = MFrame::new();
+    let shooter = m_frame.register_object(MVector::zero(), Vector2D::new(0.7, 0.0), false, 0.05);
+    let target = m_frame.register_object(MVector::new(0.0, Vector2D::new(1.0, 2.0)), Vector2D::new(0.3, 0.1), false, 0.05);
+    assert!(m_frame.add_observer(&shooter));
+    for _i in 0..500 {
+        m_frame.process_time(0.01);
+    }
+
+    let shooter_object = &m_frame.get_object_with_properties(&shooter).unwrap().0;
+    let seen = m_frame.get_observed_properties(&shooter, &target).unwrap();
+    assert!(seen.get_object_was_seen());
+    let aimed_at_seen = intercept_seen(shooter_object, seen, 0.8).unwrap();
+    // The target keeps its velocity, so what the shooter sees is where it really goes.
+    let aimed_at_object = intercept_object(shooter_object, &m_frame.get_object_with_properties(&target).unwrap().0, 0.8).unwrap();
+    assert!((aimed_at_seen.get_event().time - aimed_at_object.get_event().time).abs() < 1e-6);
+    assert!((aimed_at_seen.get_event().pos - aimed_at_object.get_event().pos).length() < 1e-6);
+    assert!((*aimed_at_seen.get_launch_velocity() - *aimed_at_object.get_launch_velocity()).length() < 1e-6);
+}
+
+#[test]
+fn test_doppler_shifted_color(){
+    let mut m_frame = MFrame::new();
+    let id = m_frame.register_object(MVector::new(0.0, Vector2D::new(3.0, 0.0)), Vector2D::new(-0.6, 0.0), true, 0.1);
+    m_frame.get_object_mut(&id).unwrap().set_spectrum(Spectrum::blackbody(3000.0));
+    let observer = m_frame.register_object(MVector::new(0.0, Vector2D::new(6.0, 0.0)), Vector2D::new(0.0, 0.0), false, 0.0);
+    assert!(m_frame.add_observer(&observer));
+    assert_eq!(m_frame.get_object_with_properties(&id).unwrap().1.get_observed_color().luminance(), 0.0);
+
+    for _i in 0..400 {
+        m_frame.process_time(0.01);
+    }
+
+    // Approaching the frame object at 0.6 doubles the frequency, receding from the observer halves it.
+    let approaching = m_frame.get_object_with_properties(&id).unwrap().1.get_observed_color();
+    let as_hotter_body = Spectrum::blackbody(6000.0).observed_color(1.0);
+    assert!((approaching.get_xyz()[2] / approaching.get_xyz()[1] - as_hotter_body.get_xyz()[2] / as_hotter_body.get_xyz()[1]).abs() < 1e-6);
+    let receding = m_frame.get_observed_properties(&observer, &id).unwrap().get_observed_color();
+    assert!(receding.luminance() < 0.05);
+    let [r, _, b] = receding.srgb8();
+    assert!(r > b);
+
+    // A new spectrum shows up even though the frequency ratio stays the same.
+    m_frame.get_object_mut(&id).unwrap().set_spectrum(Spectrum::blackbody(1500.0));
+    m_frame.process_time(0.01);
+    let approaching = m_frame.get_object_with_properties(&id).unwrap().1.get_observed_color();
+    let as_body = Spectrum::blackbody(3000.0).observed_color(1.0);
+    assert!((approaching.get_xyz()[2] / approaching.get_xyz()[1] - as_body.get_xyz()[2] / as_body.get_xyz()[1]).abs() < 1e-6);
+}
+
+#[test]
+fn test_apparent_intensity(){
+    let mut m_frame = MFrame::new();
+    let approaching = m_frame.register_object(MVector::new(0.0, Vector2D::new(3.0, 0.0)), Vector2D::new(-0.6, 0.0), true, 0.1);
+    let resting = m_frame.register_object(MVector::new(0.0, Vector2D::new(0.0, 2.0)), Vector2D::new(0.0, 0.0), true, 0.1);
+    m_frame.get_object_mut(&resting).unwrap().set_luminosity(4.0);
+    let observer = m_frame.register_object(MVector::new(0.0, Vector2D::new(6.0, 0.0)), Vector2D::new(0.0, 0.0), false, 0.0);
+    assert!(m_frame.add_observer(&observer));
+    assert_eq!(m_frame.get_object_with_properties(&approaching).unwrap().1.get_apparent_intensity(), 0.0);
+
+    for _i in 0..400 {
+        m_frame.process_time(0.01);
+    }
+
+    let resting_seen = &m_frame.get_object_with_properties(&resting).unwrap().1;
+    assert!((resting_seen.get_apparent_intensity() - 4.0 / (16.0 * PI)).abs() < 1e-9);
+
+    // Doppler factor 2 towards the frame object, 1/2 towards the observer.
+    let ahead = &m_frame.get_object_with_properties(&approaching).unwrap().1;
+    let distance = ahead.get_relative_visible_position().length();
+    assert!((ahead.get_apparent_intensity() - 16.0 / (4.0 * PI * distance * distance)).abs() < 1e-9);
+    let behind = m_frame.get_observed_properties(&observer, &approaching).unwrap();
+    let distance = behind.get_relative_visible_position().length();
+    assert!((behind.get_apparent_intensity() - 1.0 / (64.0 * PI * distance * distance)).abs() < 1e-9);
+}
+
+#[test]
+fn test_visible_outline_shows_terrell_rotation(){
+    let mut m_frame = MFrame::new();
+    let id = m_frame.register_object(MVector::new(0.0, Vector2D::new(-6.0, 10.0)), Vector2D::new(0.6, 0.0), true, 1.0);
+    let corners = vec![Vector2D::new(1.0, 1.0), Vector2D::new(-1.0, 1.0), Vector2D::new(-1.0, -1.0), Vector2D::new(1.0, -1.0)];
+    m_frame.get_object_mut(&id).unwrap().set_outline(corners.clone());
+
+    for _i in 0..2000 {
+        m_frame.process_time(0.01);
+    }
+
+    let receiver_time = m_frame.get_frame_object().get_m_pos().time;
+    let outline = m_frame.get_object_with_properties(&id).unwrap().1.get_visible_outline();
+    assert_eq!(outline.len(), 4);
+    for (seen, corner) in outline.iter().zip(&corners) {
+        // Corner at x = a + 0.6 t, contracted by 1/gamma = 0.8, seen from the origin.
+        let (a, y) = (-6.0 + 0.8 * corner.x, 10.0 + corner.y);
+        let (qa, qb, qc) = (0.64, -(2.0 * receiver_time + 1.2 * a), receiver_time * receiver_time - a * a - y * y);
+        let emmit_time = (-qb - (qb * qb - 4.0 * qa * qc).sqrt()) / (2.0 * qa);
+        assert!((*seen - Vector2D::new(a + 0.6 * emmit_time, y)).length() < 1e-6);
+    }
+    // Light from the far side left earlier, so that side lags behind and the back face shows.
+    assert!(outline[1].x - outline[2].x < -0.5);
+    assert!(outline[0].x - outline[3].x < -0.5);
+
+    m_frame.get_object_mut(&id).unwrap().set_outline(corners[..3].to_vec());
+    m_frame.process_time(0.01);
+    assert_eq!(m_frame.get_object_with_properties(&id).unwrap().1.get_visible_outline().len(), 4);
+    for _i in 0..1500 {
+        m_frame.process_time(0.01);
+    }
+    assert_eq!(m_frame.get_object_with_properties(&id).unwrap().1.get_visible_outline().len(), 3);
+}
+
+#[test]
+fn test_set_outline_keeps_motion(){
+    let mut m_frame = MFrame::new();
+    let [plain, outlined] = [(); 2].map(|_| {
+        let id = m_frame.register_object(MVector::zero(), Vector2D::new(0.6, 0.0), false, 1.0);
+        m_frame.get_object_mut(&id).unwrap().set_acceleration(Vector2D::new(0.0, 1.0));
+        id
+    });
+    for _i in 0..100 {
+        m_frame.process_time(0.01);
+    }
+    let outline = m_frame.get_object_with_properties(&outlined).unwrap().0.get_outline().to_vec();
+    m_frame.get_object_mut(&outlined).unwrap().set_outline(outline);
+    for _i in 0..100 {
+        m_frame.process_time(0.01);
+    }
+
+    let plain = &m_frame.get_object_with_properties(&plain).unwrap().0;
+    let outlined = &m_frame.get_object_with_properties(&outlined).unwrap().0;
+    assert!((plain.get_m_pos().time - 2.0).abs() < 1e-6);
+    assert!((*outlined.get_m_pos() - *plain.get_m_pos()).pos.length() < 1e-9);
+    assert!((*outlined.get_velocity() - *plain.get_velocity()).length() < 1e-9);
+    assert!((*outlined.get_velocity() - Vector2D::new(0.6, 0.678)).length() < 1e-3);
+}
+
+#[test]
+fn test_per_object_update_ratio(){
+    let mut m_frame = MFrame::new();
+    let beacons = [false, true].map(|constant_velocity|
+        m_frame.register_object_with_update_ratio(MVector::new(0.0, Vector2D::new(10.0, 0.0)), Vector2D::new(-0.6, 0.0), constant_velocity, 0.0, 0.5));
+    let debris = m_frame.register_object(MVector::new(0.0, Vector2D::new(0.0, 3.0)), Vector2D::new(0.0, 0.0), false, 0.0);
+    m_frame.get_object_mut(&debris).unwrap().set_update_ratio(2.0);
+    assert_eq!(m_frame.get_object_with_properties(&beacons[1]).unwrap().0.get_update_ratio(), 0.5);
+
+    let mut arrivals = [vec![], vec![]];
+    let mut last_ticks = [0.0; 2];
+    for _i in 0..1500 {
+        m_frame.process_time(0.01);
+        for (beacon, (arrivals, last_ticks)) in beacons.iter().zip(arrivals.iter_mut().zip(last_ticks.iter_mut())) {
+            let ticks = (m_frame.get_object_with_properties(beacon).unwrap().1.get_visible_tau() / 0.5).floor();
+            if ticks != *last_ticks {
+                arrivals.push(m_frame.get_frame_object().get_m_pos().time);
+                *last_ticks = ticks;
+            }
+        }
+    }
+    // Approaching at 0.6 doubles the frequency, so flashes every 0.5 arrive every 0.25.
+    for (beacon, arrivals) in beacons.iter().zip(&arrivals) {
+        let seen = &m_frame.get_object_with_properties(beacon).unwrap().1;
+        assert!((seen.get_observed_period() - 0.25).abs() < 1e-9);
+        assert!(arrivals.len() > 10);
+        assert!(arrivals.windows(2).all(|w| (w[1] - w[0] - 0.25).abs() < 0.011));
+    }
+    assert!((m_frame.get_object_with_properties(&debris).unwrap().1.get_observed_period() - 2.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_samples_only_on_motion_changes(){
+    let mut m_frame = MFrame::new();
+    let id = m_frame.register_object(MVector::new(0.0, Vector2D::new(100.0, 0.0)), Vector2D::new(0.0, 0.0), false, 0.1);
+    m_frame.get_object_mut(&id).unwrap().set_acceleration(Vector2D::new(0.0, 0.5));
+    m_frame.process_time(0.01);
+    let queued = m_frame.get_photon_queue_stats().get_queued_photons();
+    assert_eq!(queued, 2 * 5);
+    for _i in 0..500 {
+        m_frame.process_time(0.01);
+    }
+    assert_eq!(m_frame.get_photon_queue_stats().get_queued_photons(), queued);
+}
+
+fn receding_object(photon_queue_budget: usize, steering: bool) -> (Vec<MVector<f64>>, PhotonQueueStats){
+    let mut m_frame = MFrame::new();
+    m_frame.set_photon_queue_budget(photon_queue_budget);
+    let id = m_frame.register_object(MVector::new(0.0, Vector2D::new(1.0, 0.0)), Vector2D::new(0.9, 0.0), false, 0.1);
+    m_frame.get_object_mut(&id).unwrap().set_acceleration(Vector2D::new(0.5, 0.0));
+    let mut seen = vec![];
+    for i in 0..1000 {
+        // Every call sends a sample, even if the acceleration stays the same.
+        let angle = if steering { i as f64 / 100.0 } else { 0.0 };
+        m_frame.get_object_mut(&id).unwrap().set_acceleration(Vector2D::new(angle.cos(), angle.sin()) * 0.5);
+        m_frame.process_time(0.01);
+        seen.push(*m_frame.get_object_with_properties(&id).unwrap().1.get_visible_m_vector());
+    }
+    (seen, m_frame.get_photon_queue_stats())
+}
+
+#[test]
+fn test_bounded_photon_queues(){
+    let (unbounded, stats) = receding_object(DEFAULT_PHOTON_QUEUE_BUDGET, false);
+    assert_eq!(stats.get_coalesced_photons() + stats.get_dropped_photons(), 0);
+    assert!(stats.get_longest_queue() > 100);
+
+    // Restating the same acceleration is coalesced without changing what is seen.
+    let (bounded, stats) = receding_object(8, false);
+    assert!(stats.get_longest_queue() <= 8);
+    assert!(stats.get_coalesced_photons() > 0);
+    for (a, b) in unbounded.iter().zip(&bounded) {
+        assert!((a.time - b.time).abs() < 1e-9);
+        assert!((a.pos - b.pos).length() < 1e-9);
+    }
+
+    // Steering changes the motion with every sample, so some have to be dropped.
+    let (unbounded, _) = receding_object(DEFAULT_PHOTON_QUEUE_BUDGET, true);
+    let (bounded, stats) = receding_object(8, true);
+    assert!(stats.get_longest_queue() <= 8);
+    assert!(stats.get_queued_photons() <= 8 * 5);
+    assert!(stats.get_dropped_photons() > 0);
+    for (a, b) in unbounded.iter().zip(&bounded) {
+        assert!((a.pos - b.pos).length() < 0.05);
+    }
+}